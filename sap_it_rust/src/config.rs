@@ -2,7 +2,9 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::fmt;
 use std::path::PathBuf;
+use std::str::FromStr;
 use tracing::{debug, info};
 
 /// Application configuration containing server definitions.
@@ -31,6 +33,106 @@ pub struct Settings {
     /// Number of ping retries before giving up.
     #[serde(default = "default_ping_retries")]
     pub ping_retries: u32,
+
+    /// How host reachability is probed (icmp, tcp or both).
+    #[serde(default)]
+    pub probe_method: ProbeMethod,
+
+    /// TCP port used by the tcp probe when a server doesn't set its own.
+    #[serde(default = "default_probe_port")]
+    pub probe_port: u16,
+
+    /// Timeout in milliseconds for TCP connect probes.
+    #[serde(default = "default_tcp_timeout")]
+    pub tcp_timeout_ms: u32,
+
+    /// Seconds between background reachability checks in the TUI (0 disables).
+    #[serde(default = "default_status_interval")]
+    pub status_interval_secs: u64,
+
+    /// How the TUI runs SSH sessions.
+    #[serde(default)]
+    pub ssh_mode: SshMode,
+
+    /// RDP client tried first on Linux/macOS (Windows always uses mstsc).
+    #[serde(default)]
+    pub rdp_client: RdpClient,
+
+    /// Save TUI changes to the config file right away instead of on request.
+    #[serde(default)]
+    pub auto_save: bool,
+}
+
+/// How the TUI runs SSH sessions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SshMode {
+    /// Suspend the TUI and hand the terminal to ssh.
+    #[default]
+    External,
+    /// Run ssh in a PTY rendered inside the Connected screen.
+    Embedded,
+}
+
+impl SshMode {
+    /// Get the display name of the SSH mode.
+    pub fn name(&self) -> &'static str {
+        match self {
+            SshMode::External => "External",
+            SshMode::Embedded => "Embedded",
+        }
+    }
+}
+
+/// Preferred RDP client; the other installed clients remain fallbacks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RdpClient {
+    /// xfreerdp, then xfreerdp3, then rdesktop.
+    #[default]
+    Auto,
+    /// `xfreerdp` (FreeRDP 2.x).
+    Xfreerdp,
+    /// `xfreerdp3` (FreeRDP 3.x).
+    Xfreerdp3,
+    /// `rdesktop`.
+    Rdesktop,
+}
+
+impl RdpClient {
+    /// Get the display name of the RDP client.
+    pub fn name(&self) -> &'static str {
+        match self {
+            RdpClient::Auto => "Auto",
+            RdpClient::Xfreerdp => "xfreerdp",
+            RdpClient::Xfreerdp3 => "xfreerdp3",
+            RdpClient::Rdesktop => "rdesktop",
+        }
+    }
+}
+
+/// Method used to check whether a host is reachable.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProbeMethod {
+    /// ICMP echo via the system `ping` command.
+    #[default]
+    Icmp,
+    /// Native TCP connect to the probe port.
+    Tcp,
+    /// ICMP first, falling back to TCP; reachable if either answers.
+    Both,
+}
+
+impl ProbeMethod {
+    /// Get the display name of the probe method.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProbeMethod::Icmp => "ICMP",
+            ProbeMethod::Tcp => "TCP",
+            ProbeMethod::Both => "ICMP+TCP",
+        }
+    }
 }
 
 fn default_vpn_timeout() -> u64 {
@@ -45,52 +147,559 @@ fn default_ping_retries() -> u32 {
     3
 }
 
+fn default_probe_port() -> u16 {
+    3389
+}
+
+fn default_tcp_timeout() -> u32 {
+    2000
+}
+
+fn default_status_interval() -> u64 {
+    30
+}
+
 impl Default for Settings {
     fn default() -> Self {
         Self {
             vpn_timeout_secs: default_vpn_timeout(),
             ping_timeout_ms: default_ping_timeout(),
             ping_retries: default_ping_retries(),
+            probe_method: ProbeMethod::default(),
+            probe_port: default_probe_port(),
+            tcp_timeout_ms: default_tcp_timeout(),
+            status_interval_secs: default_status_interval(),
+            ssh_mode: SshMode::default(),
+            rdp_client: RdpClient::default(),
+            auto_save: false,
         }
     }
 }
 
 /// Server definition with connection details.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Server {
     /// Display name of the server.
     pub name: String,
 
-    /// SSH connection string (e.g., "root@192.168.0.98").
-    /// Empty or None if SSH is not available.
-    #[serde(default)]
-    pub ssh: Option<String>,
+    /// SSH connection details, either a string (e.g., "root@192.168.0.98")
+    /// or a `[servers.ssh]` table. Empty or None if SSH is not available.
+    #[serde(
+        default,
+        with = "string_or_table::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub ssh: Option<SshConfig>,
 
-    /// RDP address (IP or hostname).
-    pub rdp: String,
+    /// RDP address (IP or hostname) or a `[servers.rdp]` table.
+    #[serde(with = "string_or_table")]
+    pub rdp: RdpConfig,
 
     /// VPN connection name as configured in the system.
+    /// Empty if the server is reached without a VPN (e.g. through `jump`).
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub vpn: String,
+
+    /// Group the server is listed under (e.g. the customer).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+
+    /// Free-form tags used for filtering.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+
+    /// SSH jump host (bastion) used to reach the server, either a string
+    /// (e.g., "admin@bastion.example.com:2222") or a `[servers.jump]` table.
+    #[serde(
+        default,
+        with = "string_or_table::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub jump: Option<SshConfig>,
+
+    /// VPN backend used to bring up `vpn`.
+    /// None uses the platform default (nmcli on Linux, rasphone on Windows).
+    #[serde(default)]
+    pub vpn_backend: Option<VpnBackendConfig>,
+
+    /// Probe method override for this server.
+    #[serde(default)]
+    pub probe_method: Option<ProbeMethod>,
+
+    /// TCP probe port override for this server.
+    #[serde(default)]
+    pub probe_port: Option<u16>,
+}
+
+/// Connection details that can be written either as a short string or as a table.
+pub trait ShortForm: FromStr<Err = String> + fmt::Display {
+    /// Whether the value survives a round trip through the short string form.
+    fn fits_short_form(&self) -> bool;
+}
+
+/// Serde helpers for fields accepting `"short"` or `{ table = ... }` forms.
+mod string_or_table {
+    use super::ShortForm;
+    use serde::de::{DeserializeOwned, Error};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Repr<T> {
+        Short(String),
+        Full(T),
+    }
+
+    fn from_repr<T: ShortForm, E: Error>(repr: Repr<T>) -> Result<T, E> {
+        match repr {
+            Repr::Short(s) => s.parse().map_err(E::custom),
+            Repr::Full(value) => Ok(value),
+        }
+    }
+
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: ShortForm + Serialize,
+        S: Serializer,
+    {
+        if value.fits_short_form() {
+            serializer.serialize_str(&value.to_string())
+        } else {
+            value.serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: ShortForm + DeserializeOwned,
+        D: Deserializer<'de>,
+    {
+        from_repr(Repr::deserialize(deserializer)?)
+    }
+
+    /// The same helpers for optional fields.
+    pub mod option {
+        use super::*;
+
+        pub fn serialize<T, S>(value: &Option<T>, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            T: ShortForm + Serialize,
+            S: Serializer,
+        {
+            match value {
+                Some(value) => super::serialize(value, serializer),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+        where
+            T: ShortForm + DeserializeOwned,
+            D: Deserializer<'de>,
+        {
+            Option::<Repr<T>>::deserialize(deserializer)?
+                .map(from_repr)
+                .transpose()
+        }
+    }
+}
+
+/// Split `host`, `host:port`, `[v6]`, `[v6]:port` or a bare IPv6 address.
+fn split_host_port(s: &str) -> std::result::Result<(String, Option<u16>), String> {
+    let (host, port) = if let Some(bracketed) = s.strip_prefix('[') {
+        let (host, after) = bracketed
+            .split_once(']')
+            .ok_or_else(|| format!("Missing ']' in '{}'", s))?;
+        match after {
+            "" => (host, None),
+            _ => match after.strip_prefix(':') {
+                Some(port) => (host, Some(port)),
+                None => return Err(format!("Unexpected '{}' after ']' in '{}'", after, s)),
+            },
+        }
+    } else if s.matches(':').count() == 1 {
+        let (host, port) = s.split_once(':').unwrap_or((s, ""));
+        (host, Some(port))
+    } else {
+        // No colon, or a bare IPv6 address
+        (s, None)
+    };
+
+    if host.is_empty() {
+        return Err(format!("Missing host in '{}'", s));
+    }
+
+    let port = port
+        .map(|p| {
+            p.parse::<u16>()
+                .map_err(|_| format!("Invalid port '{}' in '{}'", p, s))
+        })
+        .transpose()?;
+
+    Ok((host.to_string(), port))
+}
+
+/// Format a host and optional port, bracketing IPv6 addresses when needed.
+fn join_host_port(f: &mut fmt::Formatter<'_>, host: &str, port: Option<u16>) -> fmt::Result {
+    match port {
+        Some(port) if host.contains(':') => write!(f, "[{}]:{}", host, port),
+        Some(port) => write!(f, "{}:{}", host, port),
+        None => write!(f, "{}", host),
+    }
+}
+
+/// SSH connection details.
+///
+/// Written either in the short form (`"user@host"`, `"user@host:2222"`,
+/// `"[fe80::1]:22"`, `"host"`) or as a `[servers.ssh]` table with `host`,
+/// `port`, `user`, `identity_file` and `options`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SshConfig {
+    /// Hostname, IPv4 or IPv6 address.
+    pub host: String,
+
+    /// Port; None uses the ssh client default (22).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+
+    /// Login user; None uses the ssh client default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+
+    /// Private key passed with `-i`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity_file: Option<PathBuf>,
+
+    /// Extra `-o` options, e.g. "ServerAliveInterval=30".
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<String>,
+}
+
+impl SshConfig {
+    /// Default SSH port.
+    pub const DEFAULT_PORT: u16 = 22;
+
+    /// Get the port, falling back to the SSH default.
+    pub fn port_or_default(&self) -> u16 {
+        self.port.unwrap_or(Self::DEFAULT_PORT)
+    }
+}
+
+impl ShortForm for SshConfig {
+    fn fits_short_form(&self) -> bool {
+        self.identity_file.is_none() && self.options.is_empty()
+    }
+}
+
+impl FromStr for SshConfig {
+    type Err = String;
+
+    /// Parse `[user@]host[:port]`, with IPv6 hosts optionally in brackets.
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Ok(SshConfig::default());
+        }
+
+        let (user, rest) = match s.rsplit_once('@') {
+            Some((user, rest)) if !user.is_empty() => (Some(user.to_string()), rest),
+            Some(_) => return Err(format!("Missing user before '@' in '{}'", s)),
+            None => (None, s),
+        };
+
+        let (host, port) = split_host_port(rest)?;
+
+        Ok(SshConfig {
+            host,
+            port,
+            user,
+            ..Default::default()
+        })
+    }
+}
+
+impl fmt::Display for SshConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(user) = &self.user {
+            write!(f, "{}@", user)?;
+        }
+        join_host_port(f, &self.host, self.port)
+    }
+}
+
+/// RDP connection details and client options.
+///
+/// Written either as a plain address (`"192.168.0.99"`, `"host:3390"`) or as
+/// a `[servers.rdp]` table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RdpConfig {
+    /// Hostname, IPv4 or IPv6 address.
+    pub host: String,
+
+    /// Port; None uses the RDP default (3389).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+
+    /// Login user name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+
+    /// Login domain.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub domain: Option<String>,
+
+    /// Fixed window size as "WIDTHxHEIGHT"; None resizes dynamically.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolution: Option<String>,
+
+    /// Start in fullscreen mode.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub fullscreen: bool,
+
+    /// Span all local monitors.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub multimon: bool,
+
+    /// Share the clipboard with the remote session.
+    #[serde(default = "default_true", skip_serializing_if = "is_true")]
+    pub clipboard: bool,
+
+    /// Local directories (or drive letters on Windows) to redirect.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub drives: Vec<String>,
+
+    /// RD Gateway host.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gateway: Option<String>,
+
+    /// Server certificate policy.
+    #[serde(default, skip_serializing_if = "CertPolicy::is_default")]
+    pub cert: CertPolicy,
+}
+
+/// How the RDP client treats the server certificate.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CertPolicy {
+    /// Accept any certificate.
+    #[default]
+    Ignore,
+    /// Trust on first use, warn when it changes.
+    Tofu,
+    /// Use the client's normal certificate verification.
+    Strict,
+}
+
+impl CertPolicy {
+    fn is_default(&self) -> bool {
+        *self == CertPolicy::default()
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn is_true(value: &bool) -> bool {
+    *value
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+impl Default for RdpConfig {
+    fn default() -> Self {
+        Self {
+            host: String::new(),
+            port: None,
+            username: None,
+            domain: None,
+            resolution: None,
+            fullscreen: false,
+            multimon: false,
+            clipboard: true,
+            drives: Vec::new(),
+            gateway: None,
+            cert: CertPolicy::default(),
+        }
+    }
+}
+
+impl RdpConfig {
+    /// Default RDP port.
+    pub const DEFAULT_PORT: u16 = 3389;
+
+    /// Get the port, falling back to the RDP default.
+    pub fn port_or_default(&self) -> u16 {
+        self.port.unwrap_or(Self::DEFAULT_PORT)
+    }
+
+    /// Parse `resolution` into width and height.
+    pub fn resolution_size(&self) -> Option<(u32, u32)> {
+        let (width, height) = self.resolution.as_ref()?.split_once(['x', 'X'])?;
+        Some((width.trim().parse().ok()?, height.trim().parse().ok()?))
+    }
+}
+
+impl ShortForm for RdpConfig {
+    fn fits_short_form(&self) -> bool {
+        *self
+            == RdpConfig {
+                host: self.host.clone(),
+                port: self.port,
+                ..Default::default()
+            }
+    }
+}
+
+impl FromStr for RdpConfig {
+    type Err = String;
+
+    /// Parse `host[:port]`, with IPv6 hosts optionally in brackets.
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Ok(RdpConfig::default());
+        }
+
+        let (host, port) = split_host_port(s)?;
+
+        Ok(RdpConfig {
+            host,
+            port,
+            ..Default::default()
+        })
+    }
+}
+
+impl fmt::Display for RdpConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        join_host_port(f, &self.host, self.port)
+    }
+}
+
+/// VPN backend selection with backend-specific parameters.
+///
+/// Written as a `[servers.vpn_backend]` table with a `type` key, e.g.
+/// `type = "openvpn"` and `config = "/etc/openvpn/client/acme.ovpn"`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum VpnBackendConfig {
+    /// NetworkManager connection named by the server's `vpn`.
+    #[serde(rename = "nmcli")]
+    Nmcli,
+
+    /// Windows RAS phonebook entry named by the server's `vpn`.
+    #[serde(rename = "rasphone")]
+    Rasphone,
+
+    /// `openvpn --config <config>`, run as a daemon.
+    #[serde(rename = "openvpn")]
+    OpenVpn {
+        /// Path to the .ovpn/.conf file.
+        config: PathBuf,
+
+        /// Extra arguments passed to openvpn.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        args: Vec<String>,
+    },
+
+    /// `wg-quick up/down <interface>`.
+    #[serde(rename = "wg-quick")]
+    WgQuick {
+        /// Interface name or config path; defaults to the server's `vpn`.
+        #[serde(default)]
+        interface: Option<String>,
+    },
+
+    /// `openconnect <server>`, run in the background.
+    #[serde(rename = "openconnect")]
+    OpenConnect {
+        /// VPN gateway URL or hostname.
+        server: String,
+
+        /// Login user name.
+        #[serde(default)]
+        user: Option<String>,
+
+        /// Protocol (anyconnect, gp, nc, pulse, ...).
+        #[serde(default)]
+        protocol: Option<String>,
+
+        /// Extra arguments passed to openconnect.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        args: Vec<String>,
+    },
 }
 
 impl Server {
     /// Check if SSH is available for this server.
     pub fn has_ssh(&self) -> bool {
-        self.ssh.as_ref().map(|s| !s.is_empty()).unwrap_or(false)
+        self.ssh_config().is_some()
+    }
+
+    /// Get the SSH connection details if available.
+    pub fn ssh_config(&self) -> Option<&SshConfig> {
+        self.ssh.as_ref().filter(|s| !s.host.is_empty())
+    }
+
+    /// Check if a VPN must be brought up for this server.
+    pub fn has_vpn(&self) -> bool {
+        !self.vpn.is_empty()
+    }
+
+    /// Get the group name, if the server belongs to one.
+    pub fn group_name(&self) -> Option<&str> {
+        self.group.as_deref().filter(|g| !g.is_empty())
+    }
+
+    /// Check if the server carries a tag (case-insensitive).
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    /// Check if the server is in `group` (when given) and carries all `tags`.
+    pub fn matches_filter(&self, group: Option<&str>, tags: &[String]) -> bool {
+        let in_group = match group {
+            Some(group) => self
+                .group_name()
+                .is_some_and(|g| g.eq_ignore_ascii_case(group)),
+            None => true,
+        };
+        in_group && tags.iter().all(|tag| self.has_tag(tag))
+    }
+
+    /// Get the jump host if one is configured.
+    pub fn jump_host(&self) -> Option<&SshConfig> {
+        self.jump.as_ref().filter(|j| !j.host.is_empty())
+    }
+
+    /// Host and TCP port that show whether the server can be reached: the
+    /// jump host if one is configured, otherwise the RDP host.
+    pub fn probe_target(&self, settings: &Settings) -> (&str, u16) {
+        match self.jump_host() {
+            Some(jump) => (&jump.host, jump.port_or_default()),
+            None => (&self.rdp.host, self.probe_port(settings)),
+        }
     }
 
     /// Get the SSH connection string if available.
-    pub fn ssh_string(&self) -> Option<&str> {
-        self.ssh
-            .as_ref()
-            .filter(|s| !s.is_empty())
-            .map(|s| s.as_str())
+    pub fn ssh_string(&self) -> Option<String> {
+        self.ssh_config().map(|s| s.to_string())
+    }
+
+    /// Get the probe method for this server, falling back to the global setting.
+    pub fn probe_method(&self, settings: &Settings) -> ProbeMethod {
+        self.probe_method.unwrap_or(settings.probe_method)
     }
 
-    /// Extract the IP address from the SSH connection string.
-    pub fn ssh_ip(&self) -> Option<String> {
-        self.ssh_string()
-            .and_then(|ssh| ssh.split('@').nth(1).map(|s| s.to_string()))
+    /// Get the TCP probe port for this server, falling back to the RDP port
+    /// and then the global setting.
+    pub fn probe_port(&self, settings: &Settings) -> u16 {
+        self.probe_port
+            .or(self.rdp.port)
+            .unwrap_or(settings.probe_port)
     }
 }
 
@@ -114,6 +723,29 @@ impl Config {
         Ok(config)
     }
 
+    /// Server indices grouped by `group`, in order of first appearance.
+    ///
+    /// Ungrouped servers come last under `None`.
+    pub fn grouped_servers(&self) -> Vec<(Option<&str>, Vec<usize>)> {
+        let mut groups: Vec<(Option<&str>, Vec<usize>)> = Vec::new();
+        let mut ungrouped = Vec::new();
+
+        for (i, server) in self.servers.iter().enumerate() {
+            match server.group_name() {
+                Some(name) => match groups.iter_mut().find(|(g, _)| *g == Some(name)) {
+                    Some((_, indices)) => indices.push(i),
+                    None => groups.push((Some(name), vec![i])),
+                },
+                None => ungrouped.push(i),
+            }
+        }
+
+        if !ungrouped.is_empty() {
+            groups.push((None, ungrouped));
+        }
+        groups
+    }
+
     /// Get the default configuration file path.
     pub fn default_path() -> PathBuf {
         // Try user config directory first, then current directory
@@ -134,27 +766,39 @@ impl Config {
             servers: vec![
                 Server {
                     name: "Ilmatex".to_string(),
-                    ssh: Some("root@192.168.0.98".to_string()),
-                    rdp: "192.168.0.99".to_string(),
+                    ssh: Some(SshConfig {
+                        host: "192.168.0.98".to_string(),
+                        user: Some("root".to_string()),
+                        ..Default::default()
+                    }),
+                    rdp: "192.168.0.99".parse().unwrap(),
                     vpn: "ILMATEX".to_string(),
+                    ..Default::default()
                 },
                 Server {
                     name: "Frodexim".to_string(),
                     ssh: None,
-                    rdp: "192.168.50.20".to_string(),
+                    rdp: "192.168.50.20".parse().unwrap(),
                     vpn: "FRODEXIM".to_string(),
+                    ..Default::default()
                 },
                 Server {
                     name: "Industrial Technic".to_string(),
-                    ssh: Some("root@192.168.100.10".to_string()),
-                    rdp: "192.168.100.20".to_string(),
+                    ssh: Some(SshConfig {
+                        host: "192.168.100.10".to_string(),
+                        user: Some("root".to_string()),
+                        ..Default::default()
+                    }),
+                    rdp: "192.168.100.20".parse().unwrap(),
                     vpn: "Industrial Technik".to_string(),
+                    ..Default::default()
                 },
                 Server {
                     name: "BG Nova".to_string(),
                     ssh: None,
-                    rdp: "192.168.100.20".to_string(),
+                    rdp: "192.168.100.20".parse().unwrap(),
                     vpn: "Industrial Technik".to_string(),
+                    ..Default::default()
                 },
             ],
             settings: Settings::default(),
@@ -177,46 +821,120 @@ mod tests {
     fn test_server_has_ssh() {
         let server_with_ssh = Server {
             name: "Test".to_string(),
-            ssh: Some("root@192.168.1.1".to_string()),
-            rdp: "192.168.1.2".to_string(),
+            ssh: Some("root@192.168.1.1".parse().unwrap()),
+            rdp: "192.168.1.2".parse().unwrap(),
             vpn: "TEST_VPN".to_string(),
+            ..Default::default()
         };
         assert!(server_with_ssh.has_ssh());
 
         let server_without_ssh = Server {
             name: "Test".to_string(),
             ssh: None,
-            rdp: "192.168.1.2".to_string(),
+            rdp: "192.168.1.2".parse().unwrap(),
             vpn: "TEST_VPN".to_string(),
+            ..Default::default()
         };
         assert!(!server_without_ssh.has_ssh());
 
         let server_empty_ssh = Server {
             name: "Test".to_string(),
-            ssh: Some("".to_string()),
-            rdp: "192.168.1.2".to_string(),
+            ssh: Some("".parse().unwrap()),
+            rdp: "192.168.1.2".parse().unwrap(),
             vpn: "TEST_VPN".to_string(),
+            ..Default::default()
         };
         assert!(!server_empty_ssh.has_ssh());
     }
 
     #[test]
-    fn test_ssh_ip_extraction() {
+    fn test_ssh_host_extraction() {
         let server = Server {
             name: "Test".to_string(),
-            ssh: Some("root@192.168.1.100".to_string()),
-            rdp: "192.168.1.2".to_string(),
+            ssh: Some("root@192.168.1.100".parse().unwrap()),
+            rdp: "192.168.1.2".parse().unwrap(),
             vpn: "TEST_VPN".to_string(),
+            ..Default::default()
         };
-        assert_eq!(server.ssh_ip(), Some("192.168.1.100".to_string()));
+        assert_eq!(
+            server.ssh_config().map(|s| s.host.as_str()),
+            Some("192.168.1.100")
+        );
 
         let server_no_ssh = Server {
             name: "Test".to_string(),
             ssh: None,
-            rdp: "192.168.1.2".to_string(),
+            rdp: "192.168.1.2".parse().unwrap(),
             vpn: "TEST_VPN".to_string(),
+            ..Default::default()
         };
-        assert_eq!(server_no_ssh.ssh_ip(), None);
+        assert_eq!(server_no_ssh.ssh_config(), None);
+    }
+
+    #[test]
+    fn test_ssh_string_parsing() {
+        let ssh: SshConfig = "admin@host.example:2222".parse().unwrap();
+        assert_eq!(ssh.user.as_deref(), Some("admin"));
+        assert_eq!(ssh.host, "host.example");
+        assert_eq!(ssh.port, Some(2222));
+
+        let ssh: SshConfig = "host.example".parse().unwrap();
+        assert_eq!(ssh.user, None);
+        assert_eq!(ssh.host, "host.example");
+        assert_eq!(ssh.port_or_default(), 22);
+
+        let ssh: SshConfig = "root@fe80::1".parse().unwrap();
+        assert_eq!(ssh.host, "fe80::1");
+        assert_eq!(ssh.port, None);
+
+        let ssh: SshConfig = "root@[fe80::1]:2200".parse().unwrap();
+        assert_eq!(ssh.host, "fe80::1");
+        assert_eq!(ssh.port, Some(2200));
+        assert_eq!(ssh.to_string(), "root@[fe80::1]:2200");
+
+        assert!("root@host:99999".parse::<SshConfig>().is_err());
+        assert!("root@".parse::<SshConfig>().is_err());
+        assert!("@host".parse::<SshConfig>().is_err());
+    }
+
+    #[test]
+    fn test_ssh_table_and_string_forms() {
+        let toml_str = r#"
+[[servers]]
+name = "Short"
+ssh = "root@10.0.0.1:2222"
+rdp = "10.0.0.2"
+vpn = "acme"
+
+[[servers]]
+name = "Table"
+rdp = "10.0.0.3"
+vpn = "acme"
+
+[servers.ssh]
+host = "10.0.0.4"
+user = "deploy"
+identity_file = "~/.ssh/acme_ed25519"
+options = ["ServerAliveInterval=30"]
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(
+            config.servers[0].ssh_string().as_deref(),
+            Some("root@10.0.0.1:2222")
+        );
+
+        let ssh = config.servers[1].ssh_config().unwrap();
+        assert_eq!(ssh.host, "10.0.0.4");
+        assert_eq!(
+            ssh.identity_file,
+            Some(PathBuf::from("~/.ssh/acme_ed25519"))
+        );
+        assert_eq!(ssh.options, vec!["ServerAliveInterval=30".to_string()]);
+
+        let serialized = toml::to_string(&config).unwrap();
+        assert!(serialized.contains(r#"ssh = "root@10.0.0.1:2222""#));
+        let round_trip: Config = toml::from_str(&serialized).unwrap();
+        assert_eq!(round_trip.servers[1].ssh, config.servers[1].ssh);
     }
 
     #[test]
@@ -227,11 +945,178 @@ mod tests {
         assert_eq!(parsed.servers.len(), config.servers.len());
     }
 
+    #[test]
+    fn test_vpn_backend_parsing() {
+        let toml_str = r#"
+[[servers]]
+name = "OpenVPN Server"
+rdp = "10.0.0.5"
+vpn = "acme"
+
+[servers.vpn_backend]
+type = "openvpn"
+config = "/etc/openvpn/client/acme.ovpn"
+
+[[servers]]
+name = "Default Server"
+rdp = "10.0.0.6"
+vpn = "acme"
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(
+            config.servers[0].vpn_backend,
+            Some(VpnBackendConfig::OpenVpn {
+                config: PathBuf::from("/etc/openvpn/client/acme.ovpn"),
+                args: Vec::new(),
+            })
+        );
+        assert_eq!(config.servers[1].vpn_backend, None);
+
+        let round_trip: Config = toml::from_str(&toml::to_string(&config).unwrap()).unwrap();
+        assert_eq!(
+            round_trip.servers[0].vpn_backend,
+            config.servers[0].vpn_backend
+        );
+    }
+
+    #[test]
+    fn test_rdp_table_and_string_forms() {
+        let toml_str = r#"
+[[servers]]
+name = "Short"
+rdp = "10.0.0.2:3390"
+vpn = "acme"
+
+[[servers]]
+name = "Table"
+vpn = "acme"
+
+[servers.rdp]
+host = "10.0.0.3"
+username = "admin"
+domain = "CORP"
+resolution = "1920x1080"
+clipboard = false
+drives = ["/home/me/share"]
+gateway = "gw.example.com"
+cert = "tofu"
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.servers[0].rdp.host, "10.0.0.2");
+        assert_eq!(config.servers[0].rdp.port, Some(3390));
+        assert_eq!(config.servers[0].probe_port(&config.settings), 3390);
+
+        let rdp = &config.servers[1].rdp;
+        assert_eq!(rdp.username.as_deref(), Some("admin"));
+        assert_eq!(rdp.resolution_size(), Some((1920, 1080)));
+        assert!(!rdp.clipboard);
+        assert_eq!(rdp.cert, CertPolicy::Tofu);
+
+        let serialized = toml::to_string(&config).unwrap();
+        assert!(serialized.contains(r#"rdp = "10.0.0.2:3390""#));
+        let round_trip: Config = toml::from_str(&serialized).unwrap();
+        assert_eq!(&round_trip.servers[1].rdp, rdp);
+    }
+
+    #[test]
+    fn test_jump_host_without_vpn() {
+        let toml_str = r#"
+[[servers]]
+name = "Behind Bastion"
+rdp = "10.1.0.20"
+ssh = "root@10.1.0.10"
+jump = "admin@bastion.example.com:2222"
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        let server = &config.servers[0];
+        assert!(!server.has_vpn());
+
+        let jump = server.jump_host().unwrap();
+        assert_eq!(jump.host, "bastion.example.com");
+        assert_eq!(jump.user.as_deref(), Some("admin"));
+        assert_eq!(jump.port, Some(2222));
+    }
+
+    #[test]
+    fn test_groups_and_tags() {
+        let toml_str = r#"
+[[servers]]
+name = "A1"
+rdp = "10.0.0.1"
+group = "Acme"
+tags = ["prod", "sap"]
+
+[[servers]]
+name = "Loose"
+rdp = "10.0.0.2"
+
+[[servers]]
+name = "B1"
+rdp = "10.0.0.3"
+group = "Beta"
+tags = ["test"]
+
+[[servers]]
+name = "A2"
+rdp = "10.0.0.4"
+group = "Acme"
+tags = ["test"]
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(
+            config.grouped_servers(),
+            vec![
+                (Some("Acme"), vec![0, 3]),
+                (Some("Beta"), vec![2]),
+                (None, vec![1]),
+            ]
+        );
+
+        let a1 = &config.servers[0];
+        assert!(a1.matches_filter(Some("acme"), &[]));
+        assert!(a1.matches_filter(None, &["PROD".to_string(), "sap".to_string()]));
+        assert!(!a1.matches_filter(None, &["test".to_string()]));
+        assert!(!config.servers[1].matches_filter(Some("Acme"), &[]));
+    }
+
     #[test]
     fn test_default_settings() {
         let settings = Settings::default();
         assert_eq!(settings.vpn_timeout_secs, 30);
         assert_eq!(settings.ping_timeout_ms, 3000);
         assert_eq!(settings.ping_retries, 3);
+        assert_eq!(settings.probe_method, ProbeMethod::Icmp);
+        assert_eq!(settings.probe_port, 3389);
+        assert_eq!(settings.tcp_timeout_ms, 2000);
+        assert_eq!(settings.status_interval_secs, 30);
+        assert_eq!(settings.ssh_mode, SshMode::External);
+        assert_eq!(settings.rdp_client, RdpClient::Auto);
+        assert!(!settings.auto_save);
+    }
+
+    #[test]
+    fn test_probe_overrides() {
+        let toml_str = r#"
+[settings]
+probe_method = "both"
+
+[[servers]]
+name = "Firewalled"
+rdp = "10.0.0.5"
+vpn = "acme"
+probe_method = "tcp"
+probe_port = 3390
+
+[[servers]]
+name = "Default"
+rdp = "10.0.0.6"
+vpn = "acme"
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        let settings = &config.settings;
+        assert_eq!(config.servers[0].probe_method(settings), ProbeMethod::Tcp);
+        assert_eq!(config.servers[0].probe_port(settings), 3390);
+        assert_eq!(config.servers[1].probe_method(settings), ProbeMethod::Both);
+        assert_eq!(config.servers[1].probe_port(settings), 3389);
     }
 }
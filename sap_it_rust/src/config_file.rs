@@ -0,0 +1,572 @@
+//! Format-preserving edits of the configuration file.
+//!
+//! Serializing a `Config` with `toml::to_string_pretty` drops every comment
+//! and reorders keys. `ConfigFile` instead keeps the parsed document and only
+//! touches the keys whose values actually changed.
+
+use crate::config::{Config, Server};
+use crate::validate::{self, Issue};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use toml_edit::{ArrayOfTables, DocumentMut, Item, Table, Value};
+
+/// A configuration file opened for editing.
+pub struct ConfigFile {
+    path: PathBuf,
+    document: DocumentMut,
+    /// Content as read, to tell problems the edits caused from older ones.
+    original: String,
+}
+
+impl ConfigFile {
+    /// Open and parse the file at `path`.
+    pub fn open(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+        let document = content
+            .parse::<DocumentMut>()
+            .with_context(|| format!("Failed to parse config file: {}", path.display()))?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            document,
+            original: content,
+        })
+    }
+
+    /// Open the file at `path`, or start an empty document if it doesn't exist.
+    pub fn open_or_new(path: &Path) -> Result<Self> {
+        if path.exists() {
+            return Self::open(path);
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            document: DocumentMut::new(),
+            original: String::new(),
+        })
+    }
+
+    /// The configuration as currently edited.
+    pub fn config(&self) -> Result<Config> {
+        toml::from_str(&self.document.to_string()).context("Failed to parse edited configuration")
+    }
+
+    /// The `[[servers]]` entries, created if the file has none.
+    fn servers_mut(&mut self) -> Result<&mut ArrayOfTables> {
+        let servers = self
+            .document
+            .entry("servers")
+            .or_insert(Item::ArrayOfTables(ArrayOfTables::new()));
+        servers
+            .as_array_of_tables_mut()
+            .context("servers must be written as [[servers]] tables to be edited")
+    }
+
+    /// Append a server.
+    pub fn add_server(&mut self, server: &Server) -> Result<()> {
+        let table = to_table(server)?;
+        self.servers_mut()?.push(table);
+        Ok(())
+    }
+
+    /// Replace the server at `index`, keeping comments and the formatting of
+    /// unchanged keys.
+    pub fn set_server(&mut self, index: usize, server: &Server) -> Result<()> {
+        let current = match self.config()?.servers.get(index) {
+            Some(current) => to_table(current)?,
+            None => anyhow::bail!("No server at index {}", index + 1),
+        };
+        let existing = self
+            .servers_mut()?
+            .get_mut(index)
+            .with_context(|| format!("No server at index {}", index + 1))?;
+        sync_table(existing, &current, &to_table(server)?);
+        Ok(())
+    }
+
+    /// Remove the server at `index`.
+    pub fn remove_server(&mut self, index: usize) -> Result<()> {
+        let servers = self.servers_mut()?;
+        if index >= servers.len() {
+            anyhow::bail!("No server at index {}", index + 1);
+        }
+        servers.remove(index);
+        Ok(())
+    }
+
+    /// Bring the file in line with `config`, touching only what changed.
+    ///
+    /// Servers are matched to the existing entries by name, or by position
+    /// when renamed, so their comments follow them when the list is reordered.
+    pub fn update(&mut self, config: &Config) -> Result<()> {
+        let current = self.config()?;
+
+        if to_table(&current.settings)?.to_string() != to_table(&config.settings)?.to_string() {
+            let settings = self
+                .document
+                .entry("settings")
+                .or_insert(Item::Table(Table::new()))
+                .as_table_mut()
+                .context("settings must be written as a [settings] table to be edited")?;
+            sync_table(
+                settings,
+                &to_table(&current.settings)?,
+                &to_table(&config.settings)?,
+            );
+        }
+
+        let servers = self.servers_mut()?;
+        let mut existing: Vec<Option<(&Server, Table)>> = std::mem::take(servers)
+            .into_iter()
+            .zip(&current.servers)
+            .map(|(table, server)| Some((server, table)))
+            .collect();
+
+        for (index, server) in config.servers.iter().enumerate() {
+            let renamed = |entry: &Option<(&Server, Table)>| {
+                entry
+                    .as_ref()
+                    .is_some_and(|(old, _)| !config.servers.iter().any(|s| s.name == old.name))
+            };
+            let matched = existing
+                .iter()
+                .position(|entry| {
+                    entry
+                        .as_ref()
+                        .is_some_and(|(old, _)| old.name == server.name)
+                })
+                .or_else(|| existing.get(index).filter(|e| renamed(e)).map(|_| index));
+
+            let table = match matched.and_then(|i| existing[i].take()) {
+                Some((old, mut table)) => {
+                    sync_table(&mut table, &to_table(old)?, &to_table(server)?);
+                    table
+                }
+                None => to_table(server)?,
+            };
+            servers.push(table);
+        }
+
+        renumber(servers);
+        Ok(())
+    }
+
+    /// The file content after the edits.
+    pub fn content(&self) -> String {
+        self.document.to_string()
+    }
+
+    /// Write the edited file, refusing edits that introduce problems
+    /// `validate` reports.
+    ///
+    /// Problems the file already had don't block the save; they are returned
+    /// so the caller can warn about them.
+    ///
+    /// The content goes to a temporary file that replaces the original in a
+    /// single rename, so a crash never leaves a half-written config behind;
+    /// the previous version is kept next to it with a `.bak` suffix.
+    pub fn save(&self) -> Result<Vec<Issue>> {
+        let content = self.content();
+        let (new, existing) = new_issues(&self.original, &content);
+        if let Some(issue) = new.first() {
+            anyhow::bail!(
+                "Not saved, the configuration would be invalid: {} ({} new problem(s) in total)",
+                issue.message,
+                new.len()
+            );
+        }
+
+        // Replace the file a symlink points to rather than the link itself
+        let path = fs::canonicalize(&self.path).unwrap_or_else(|_| self.path.clone());
+        let temp = sibling(&path, ".tmp");
+        write_new(&temp, &path, &content)
+            .with_context(|| format!("Failed to write config file: {}", temp.display()))?;
+
+        if path.exists() {
+            let backup = sibling(&path, ".bak");
+            if let Err(e) = fs::copy(&path, &backup) {
+                let _ = fs::remove_file(&temp);
+                return Err(e)
+                    .with_context(|| format!("Failed to write backup file: {}", backup.display()));
+            }
+        }
+
+        fs::rename(&temp, &path).map_err(|e| {
+            let _ = fs::remove_file(&temp);
+            anyhow::Error::new(e)
+                .context(format!("Failed to write config file: {}", path.display()))
+        })?;
+
+        Ok(existing)
+    }
+}
+
+/// Split the problems of `edited` into new ones and those `original` had.
+///
+/// Issues are matched by message, as edits move the lines they are on.
+fn new_issues(original: &str, edited: &str) -> (Vec<Issue>, Vec<Issue>) {
+    let mut before: HashMap<String, usize> = HashMap::new();
+    for issue in validate::validate(original) {
+        *before.entry(issue.message).or_default() += 1;
+    }
+
+    validate::validate(edited)
+        .into_iter()
+        .partition(|issue| match before.get_mut(&issue.message) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                false
+            }
+            _ => true,
+        })
+}
+
+/// `path` with `suffix` appended to its file name.
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.file_name().unwrap_or_default());
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// Write `content` to `temp` with the permissions of `original`, flushed to disk.
+fn write_new(temp: &Path, original: &Path, content: &str) -> std::io::Result<()> {
+    let mut file = fs::File::create(temp)?;
+    file.write_all(content.as_bytes())?;
+    if let Ok(metadata) = fs::metadata(original) {
+        file.set_permissions(metadata.permissions())?;
+    }
+    file.sync_all()
+}
+
+/// A value serialized as a standalone table.
+fn to_table<T: Serialize>(value: &T) -> Result<Table> {
+    let content = toml::to_string(value).context("Failed to serialize configuration")?;
+    let document = content
+        .parse::<DocumentMut>()
+        .context("Failed to parse serialized configuration")?;
+    Ok(detached(document.as_table()))
+}
+
+/// Apply the keys that differ between the serialized `current` and `new`
+/// versions of `existing`.
+///
+/// Keys may be spelled differently in the file than `toml` writes them (an
+/// inline `rdp` table instead of "host:port", defaults left out), so only
+/// the serialized forms tell what actually changed.
+fn sync_table(existing: &mut Table, current: &Table, new: &Table) {
+    let changed: Vec<&str> = new
+        .iter()
+        .chain(current.iter())
+        .map(|(key, _)| key)
+        .filter(|&key| {
+            let text = |table: &Table| table.get(key).map(|item| item.to_string());
+            text(new) != text(current)
+        })
+        .collect();
+
+    for key in changed {
+        match new.get(key) {
+            Some(item) => merge_item(existing, key, item),
+            None => {
+                existing.remove(key);
+            }
+        }
+    }
+}
+
+/// Give the server tables the document positions they already occupy, in
+/// list order, so the file follows the order of `servers`.
+fn renumber(servers: &mut ArrayOfTables) {
+    fn positions(table: &Table, out: &mut Vec<usize>) {
+        out.extend(table.position());
+        for (_, item) in table.iter() {
+            if let Item::Table(sub) = item {
+                positions(sub, out);
+            }
+        }
+    }
+    fn assign(table: &mut Table, slots: &mut impl Iterator<Item = usize>) {
+        if table.position().is_some() {
+            if let Some(position) = slots.next() {
+                table.set_position(position);
+            }
+        }
+        for (_, item) in table.iter_mut() {
+            if let Item::Table(sub) = item {
+                assign(sub, slots);
+            }
+        }
+    }
+
+    let mut slots = Vec::new();
+    for table in servers.iter() {
+        positions(table, &mut slots);
+    }
+    slots.sort_unstable();
+    let mut slots = slots.into_iter();
+    for table in servers.iter_mut() {
+        assign(table, &mut slots);
+    }
+}
+
+/// Copy of `source` without document positions, so its sub-tables are
+/// written right after it wherever it is inserted.
+fn detached(source: &Table) -> Table {
+    let mut table = Table::new();
+    for (key, item) in source.iter() {
+        let item = match item {
+            Item::Table(sub) => Item::Table(detached(sub)),
+            other => other.clone(),
+        };
+        table.insert(key, item);
+    }
+    table
+}
+
+/// Update `existing` to the content of `new`, only replacing changed values.
+fn merge_table(existing: &mut Table, new: &Table) {
+    let stale: Vec<String> = existing
+        .iter()
+        .map(|(key, _)| key.to_string())
+        .filter(|key| !new.contains_key(key))
+        .collect();
+    for key in stale {
+        existing.remove(&key);
+    }
+
+    for (key, item) in new.iter() {
+        merge_item(existing, key, item);
+    }
+}
+
+/// Set `key` in `existing` to `item`, keeping the formatting of the old value.
+fn merge_item(existing: &mut Table, key: &str, item: &Item) {
+    let Some(old) = existing.get_mut(key) else {
+        existing.insert(key, detached_item(item));
+        return;
+    };
+
+    match (old, item) {
+        (Item::Table(old), Item::Table(new)) => merge_table(old, new),
+        // Keep a sub-table the user wrote inline as an inline table
+        (Item::Value(old @ Value::InlineTable(_)), Item::Table(new)) => {
+            replace_value(old, Value::InlineTable(new.clone().into_inline_table()));
+        }
+        (Item::Value(old), Item::Value(new)) => replace_value(old, new.clone()),
+        (old, new) => *old = detached_item(new),
+    }
+}
+
+/// `item` without document positions.
+fn detached_item(item: &Item) -> Item {
+    match item {
+        Item::Table(table) => Item::Table(detached(table)),
+        other => other.clone(),
+    }
+}
+
+/// Replace `old` with `new` unless they are equal, keeping the comments and
+/// whitespace around the value.
+fn replace_value(old: &mut Value, mut new: Value) {
+    if same_value(old, &new) {
+        return;
+    }
+    *new.decor_mut() = old.decor().clone();
+    *old = new;
+}
+
+/// Whether two values are equal, ignoring formatting.
+fn same_value(a: &Value, b: &Value) -> bool {
+    let parse = |value: &Value| {
+        let mut value = value.clone();
+        value.decor_mut().clear();
+        toml::Value::deserialize(toml::de::ValueDeserializer::new(&value.to_string())).ok()
+    };
+    match (parse(a), parse(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"# Global settings
+[settings]
+ping_retries = 3 # keep it low
+
+# Production
+[[servers]]
+name = "Ilmatex"
+rdp = "192.168.0.99"   # RDP host
+vpn = "ILMATEX"
+
+[[servers]]
+name = "Frodexim"
+rdp = { host = "192.168.50.20", port = 3390 }
+"#;
+
+    fn open(content: &str) -> (tempfile::TempDir, ConfigFile) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("servers.toml");
+        std::fs::write(&path, content).unwrap();
+        let file = ConfigFile::open(&path).unwrap();
+        (dir, file)
+    }
+
+    #[test]
+    fn test_unchanged_server_keeps_formatting() {
+        let (_dir, mut file) = open(SOURCE);
+        let config = file.config().unwrap();
+        for (i, server) in config.servers.iter().enumerate() {
+            file.set_server(i, server).unwrap();
+        }
+        assert_eq!(file.content(), SOURCE);
+    }
+
+    #[test]
+    fn test_edit_only_touches_changed_keys() {
+        let (_dir, mut file) = open(SOURCE);
+        let config = file.config().unwrap();
+
+        let mut server = config.servers[0].clone();
+        server.vpn = String::new();
+        server.rdp.port = Some(3390);
+        server.tags = vec!["prod".to_string()];
+        file.set_server(0, &server).unwrap();
+
+        let mut server = config.servers[1].clone();
+        server.rdp.port = None;
+        file.set_server(1, &server).unwrap();
+
+        let content = file.content();
+        assert!(content.contains("# Production\n[[servers]]\nname = \"Ilmatex\"\n"));
+        assert!(content.contains("rdp = \"192.168.0.99:3390\"   # RDP host\n"));
+        assert!(!content.contains("ILMATEX"));
+        assert!(content.contains("tags = [\"prod\"]"));
+        assert!(content.contains("rdp = \"192.168.50.20\"\n"));
+        assert!(content.contains("ping_retries = 3 # keep it low"));
+    }
+
+    #[test]
+    fn test_add_and_remove_servers() {
+        let (_dir, mut file) = open(SOURCE);
+        let mut server = Server {
+            name: "Nova".to_string(),
+            rdp: "10.0.0.1".parse().unwrap(),
+            ..Default::default()
+        };
+        server.rdp.fullscreen = true;
+        file.add_server(&server).unwrap();
+        file.remove_server(0).unwrap();
+        assert!(file.remove_server(5).is_err());
+
+        let content = file.content();
+        assert!(content.starts_with("# Global settings\n[settings]\n"));
+        assert!(content.ends_with(
+            "[[servers]]\nname = \"Nova\"\n\n[servers.rdp]\nhost = \"10.0.0.1\"\nfullscreen = true\n"
+        ));
+
+        let names: Vec<String> = file
+            .config()
+            .unwrap()
+            .servers
+            .into_iter()
+            .map(|s| s.name)
+            .collect();
+        assert_eq!(names, ["Frodexim", "Nova"]);
+    }
+
+    #[test]
+    fn test_update_keeps_comments_when_reordering() {
+        let (_dir, mut file) = open(SOURCE);
+        let mut config = file.config().unwrap();
+        file.update(&config).unwrap();
+        assert_eq!(file.content(), SOURCE);
+
+        config.servers.swap(0, 1);
+        config.settings.ping_timeout_ms = 500;
+        file.update(&config).unwrap();
+        // Renamed servers are matched by position
+        config.servers[0].name = "Frodexim Old".to_string();
+        file.update(&config).unwrap();
+
+        let content = file.content();
+        assert!(
+            content.contains("[settings]\nping_retries = 3 # keep it low\nping_timeout_ms = 500\n")
+        );
+        assert!(content.contains("[[servers]]\nname = \"Frodexim Old\"\nrdp = { host = \"192.168.50.20\", port = 3390 }\n"));
+        assert!(content.ends_with("# Production\n[[servers]]\nname = \"Ilmatex\"\nrdp = \"192.168.0.99\"   # RDP host\nvpn = \"ILMATEX\"\n"));
+        assert_eq!(file.config().unwrap().servers.len(), 2);
+    }
+
+    #[test]
+    fn test_save_keeps_backup() {
+        let (dir, mut file) = open(SOURCE);
+        let mut config = file.config().unwrap();
+        config.servers.remove(1);
+        file.update(&config).unwrap();
+        file.save().unwrap();
+
+        let path = dir.path().join("servers.toml");
+        let saved = std::fs::read_to_string(&path).unwrap();
+        assert!(!saved.contains("Frodexim"));
+        let backup = std::fs::read_to_string(dir.path().join("servers.toml.bak")).unwrap();
+        assert_eq!(backup, SOURCE);
+        assert!(!dir.path().join("servers.toml.tmp").exists());
+
+        // A new file gets every server but no backup
+        let path = dir.path().join("new.toml");
+        let mut file = ConfigFile::open_or_new(&path).unwrap();
+        file.update(&config).unwrap();
+        file.save().unwrap();
+        let saved: Config = toml::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved.servers[0].name, "Ilmatex");
+        assert!(!dir.path().join("new.toml.bak").exists());
+    }
+
+    #[test]
+    fn test_save_rejects_invalid_config() {
+        let (dir, mut file) = open(SOURCE);
+        let mut server = file.config().unwrap().servers[0].clone();
+        server.name = "Frodexim".to_string();
+        file.set_server(0, &server).unwrap();
+
+        assert!(file.save().is_err());
+        let on_disk = std::fs::read_to_string(dir.path().join("servers.toml")).unwrap();
+        assert_eq!(on_disk, SOURCE);
+    }
+
+    #[test]
+    fn test_save_allows_existing_problems() {
+        let source = format!("{}vpn = \"\"\ncolour = \"blue\"\n", SOURCE);
+        let (dir, mut file) = open(&source);
+        let mut server = file.config().unwrap().servers[0].clone();
+        server.rdp.port = Some(3390);
+        file.set_server(0, &server).unwrap();
+
+        // The edit is saved, and the problems it didn't cause are reported
+        let existing = file.save().unwrap();
+        let messages: Vec<&str> = existing.iter().map(|i| i.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "vpn is empty; remove it for servers reached without a VPN",
+                "unknown key 'colour' in server 'Frodexim'",
+            ]
+        );
+
+        // A new problem is still refused
+        let mut file = ConfigFile::open(&dir.path().join("servers.toml")).unwrap();
+        server.rdp.host = "192.168.0.300".to_string();
+        file.set_server(0, &server).unwrap();
+        let error = file.save().unwrap_err().to_string();
+        assert!(error.contains("invalid rdp host"), "{}", error);
+    }
+}
@@ -1,15 +1,22 @@
 //! Connection management module with graceful shutdown support.
 
-use crate::config::{Server, Settings};
+use crate::config::{ProbeMethod, RdpConfig, Server, Settings, SshConfig};
 use crate::platform;
+use crate::platform::ssh::Tunnel;
+use crate::platform::vpn::{self, VpnBackend, VpnError};
 use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use tracing::{debug, error, info, warn};
 
 /// Connection type options.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ConnectionType {
     Rdp,
     Ssh,
@@ -36,33 +43,434 @@ impl ConnectionType {
     }
 }
 
+/// Progress of a connection, reported to the manager's event callback.
+///
+/// Serialized as `{"event": "vpn_connecting", "vpn": ..., ...}` for the
+/// `connect --json` event stream.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ConnectEvent {
+    /// Bringing up the VPN.
+    VpnConnecting { vpn: String, backend: String },
+    /// The VPN was already up before sap_it; it is left alone on exit.
+    VpnAlreadyUp { vpn: String },
+    /// The VPN client started; waiting for the tunnel to come up.
+    VpnWaiting,
+    /// The VPN is up (the probe target answered).
+    VpnUp,
+    /// The VPN did not answer within the timeout; continuing anyway.
+    VpnTimeout,
+    /// Probing a host before starting a session.
+    Probing { host: String },
+    /// The host answered.
+    ProbeOk { host: String },
+    /// The host did not answer after all retries.
+    ProbeFailed { host: String },
+    /// The SSH tunnel through the jump host is open.
+    TunnelOpen { local_port: u16 },
+    /// A client session was started.
+    SessionStarted { session: ConnectionType },
+    /// One session of a `Both` connection could not be started; the other
+    /// one goes ahead.
+    SessionSkipped {
+        session: ConnectionType,
+        error: String,
+        hint: String,
+    },
+    /// The sessions ended.
+    SessionEnded,
+}
+
+/// Why a connection could not be established.
+///
+/// Each variant maps to its own process exit code (see `exit_code`), so
+/// scripts running `sap_it connect` can tell failures apart.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectError {
+    /// The VPN could not be brought up.
+    Vpn(VpnError),
+    /// The host did not answer after all probe retries.
+    Unreachable { host: String },
+    /// A client program (RDP client, ssh, VPN client) is not installed.
+    ClientMissing { client: &'static str },
+    /// A client program is installed but failed to start or exited early.
+    Spawn {
+        client: &'static str,
+        message: String,
+    },
+    /// Credentials were rejected.
+    Auth { target: String },
+    /// An SSH session was requested for a server without SSH.
+    NoSsh { server: String },
+    /// Shutdown (Ctrl+C) or cancellation was requested.
+    Shutdown,
+}
+
+impl ConnectError {
+    /// Process exit code for the CLI.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ConnectError::Vpn(_) => 3,
+            ConnectError::Unreachable { .. } => 4,
+            ConnectError::ClientMissing { .. } => 5,
+            ConnectError::Spawn { .. } => 6,
+            ConnectError::Auth { .. } => 7,
+            ConnectError::NoSsh { .. } => 8,
+            // Conventional code for termination by SIGINT
+            ConnectError::Shutdown => 130,
+        }
+    }
+
+    /// What the user can do about the error.
+    pub fn hint(&self) -> String {
+        match self {
+            ConnectError::Vpn(e) => e.hint(),
+            ConnectError::Unreachable { host } => format!(
+                "Check that {} is running and reachable from here (VPN, firewall, probe_method)",
+                host
+            ),
+            ConnectError::ClientMissing { client } => {
+                format!("Install {} and make sure it is on the PATH", client)
+            }
+            ConnectError::Spawn { .. } => "Run with --verbose for details".to_string(),
+            ConnectError::Auth { .. } => {
+                "Check the username, password, key or certificate".to_string()
+            }
+            ConnectError::NoSsh { .. } => {
+                "Add an ssh entry for the server, or connect with RDP only".to_string()
+            }
+            ConnectError::Shutdown => "Connect again when ready".to_string(),
+        }
+    }
+
+    /// Classify a failure to run `client`: not installed, or failed to start.
+    fn client(client: &'static str, error: anyhow::Error) -> Self {
+        let missing = error.chain().any(|cause| {
+            cause
+                .downcast_ref::<io::Error>()
+                .is_some_and(|e| e.kind() == io::ErrorKind::NotFound)
+        });
+        if missing {
+            ConnectError::ClientMissing { client }
+        } else {
+            ConnectError::Spawn {
+                client,
+                message: format!("{:#}", error),
+            }
+        }
+    }
+
+    /// Classify a failure of VPN backend `backend` to bring up `vpn`.
+    fn vpn(vpn: &str, backend: &'static str, error: anyhow::Error) -> Self {
+        match error.downcast::<VpnError>() {
+            Ok(e) => e.into(),
+            Err(error) => match ConnectError::client(backend, error) {
+                ConnectError::Spawn { message, .. } => ConnectError::Vpn(VpnError::Failed {
+                    vpn: vpn.to_string(),
+                    message,
+                }),
+                missing => missing,
+            },
+        }
+    }
+}
+
+impl From<VpnError> for ConnectError {
+    fn from(error: VpnError) -> Self {
+        match error {
+            VpnError::BackendMissing { backend } => ConnectError::ClientMissing { client: backend },
+            VpnError::AuthFailed { vpn } => ConnectError::Auth {
+                target: format!("VPN '{}'", vpn),
+            },
+            other => ConnectError::Vpn(other),
+        }
+    }
+}
+
+impl fmt::Display for ConnectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConnectError::Vpn(e) => write!(f, "{}", e),
+            ConnectError::Unreachable { host } => write!(f, "Host {} is not reachable", host),
+            ConnectError::ClientMissing { client } => write!(f, "{} is not installed", client),
+            ConnectError::Spawn { client, message } => write!(f, "{} failed: {}", client, message),
+            ConnectError::Auth { target } => write!(f, "Authentication failed for {}", target),
+            ConnectError::NoSsh { server } => {
+                write!(f, "SSH not available for server '{}'", server)
+            }
+            ConnectError::Shutdown => write!(f, "Shutdown requested"),
+        }
+    }
+}
+
+impl std::error::Error for ConnectError {}
+
+/// Callback receiving connection progress events.
+pub type EventSink = Box<dyn Fn(ConnectEvent) + Send + Sync>;
+
+/// Number of connections using each VPN, shared between concurrent managers.
+///
+/// The first connection brings a VPN up and the last one tears it down, so
+/// servers behind the same tunnel can be connected independently.
+///
+/// Each VPN has its own lock, held while it is brought up or torn down:
+/// sessions on the same VPN wait for each other there, so only successful
+/// connects are counted.
+#[derive(Debug, Clone, Default)]
+pub struct VpnUsage(Arc<Mutex<HashMap<String, Arc<Mutex<usize>>>>>);
+
+impl VpnUsage {
+    /// The user count of `vpn`, locked.
+    fn entry(&self, vpn: &str) -> Arc<Mutex<usize>> {
+        let mut counts = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        counts.entry(vpn.to_string()).or_default().clone()
+    }
+
+    /// Register a user of `vpn`, calling `connect` if it is the first one.
+    ///
+    /// `connect` returns whether the session now owns the VPN (false when it
+    /// was up before sap_it); a failed or unowned connect isn't counted, and
+    /// the next session to wait here tries again. Returns whether the
+    /// session was counted and must `release` the VPN.
+    pub fn acquire<E>(
+        &self,
+        vpn: &str,
+        connect: impl FnOnce() -> Result<bool, E>,
+    ) -> Result<bool, E> {
+        let entry = self.entry(vpn);
+        let mut count = entry.lock().unwrap_or_else(|e| e.into_inner());
+        if *count == 0 && !connect()? {
+            return Ok(false);
+        }
+        *count += 1;
+        Ok(true)
+    }
+
+    /// Unregister a user of `vpn`, calling `disconnect` if it was the last
+    /// one; returns true in that case.
+    pub fn release(&self, vpn: &str, disconnect: impl FnOnce()) -> bool {
+        let entry = self.entry(vpn);
+        let mut count = entry.lock().unwrap_or_else(|e| e.into_inner());
+        *count = count.saturating_sub(1);
+        if *count == 0 {
+            disconnect();
+            return true;
+        }
+        false
+    }
+
+    /// Number of connections currently using `vpn`.
+    pub fn count(&self, vpn: &str) -> usize {
+        *self.entry(vpn).lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Check whether a host is reachable using the given probe method.
+pub fn probe_host(host: &str, method: ProbeMethod, port: u16, settings: &Settings) -> bool {
+    match method {
+        ProbeMethod::Icmp => platform::ping_host(host, settings.ping_timeout_ms),
+        ProbeMethod::Tcp => platform::tcp_probe(host, port, settings.tcp_timeout_ms),
+        ProbeMethod::Both => {
+            platform::ping_host(host, settings.ping_timeout_ms)
+                || platform::tcp_probe(host, port, settings.tcp_timeout_ms)
+        }
+    }
+}
+
+/// Exit status ssh uses for its own errors, as opposed to the remote shell's.
+const SSH_ERROR_STATUS: i32 = 255;
+
+/// What ssh prints when it can't reach a host at all.
+const SSH_UNREACHABLE: &[&str] = &[
+    "connection refused",
+    "connection timed out",
+    "operation timed out",
+    "no route to host",
+    "network is unreachable",
+    "could not resolve hostname",
+];
+
+/// Interpret how an interactive ssh session to `target` ended, given its
+/// exit code and the end of its error output.
+///
+/// ssh exits with 255 for any error of its own, whether the login was
+/// rejected, the connection failed or was dropped, the host key changed or
+/// a jump hop failed; the probe beforehand only shows that a port answered.
+/// Its message tells these apart: only "Permission denied" is a failed
+/// login. Any other exit code is the remote shell's, so the session ran.
+pub fn check_ssh_exit(
+    target: &SshConfig,
+    code: Option<i32>,
+    errors: &str,
+) -> Result<(), ConnectError> {
+    if code != Some(SSH_ERROR_STATUS) {
+        debug!("ssh exited with {:?}", code);
+        return Ok(());
+    }
+
+    let lower = errors.to_lowercase();
+    if lower.contains("permission denied") {
+        return Err(ConnectError::Auth {
+            target: format!("SSH {}", target),
+        });
+    }
+
+    let message = errors
+        .lines()
+        .map(str::trim)
+        .rfind(|line| !line.is_empty())
+        .unwrap_or("ssh exited with status 255")
+        .to_string();
+    if SSH_UNREACHABLE
+        .iter()
+        .any(|pattern| lower.contains(pattern))
+    {
+        warn!("{}", message);
+        return Err(ConnectError::Unreachable {
+            host: target.host.clone(),
+        });
+    }
+    Err(ConnectError::Spawn {
+        client: "ssh",
+        message,
+    })
+}
+
+/// Forward the server's RDP port through its jump host.
+///
+/// Returns the tunnel together with RDP options pointing at its local end.
+pub fn tunnel_rdp(server: &Server, jump: &SshConfig) -> Result<(Tunnel, RdpConfig)> {
+    info!("Opening SSH tunnel to {} via {}...", server.rdp, jump);
+    let tunnel = Tunnel::open(jump, &server.rdp.host, server.rdp.port_or_default())
+        .with_context(|| format!("Failed to open SSH tunnel via {}", jump))?;
+
+    let rdp = RdpConfig {
+        host: "127.0.0.1".to_string(),
+        port: Some(tunnel.local_port()),
+        ..server.rdp.clone()
+    };
+
+    Ok((tunnel, rdp))
+}
+
 /// Manages server connections with automatic cleanup.
 pub struct ConnectionManager {
     server: Server,
     settings: Settings,
+    vpn: Box<dyn VpnBackend>,
     vpn_connected: AtomicBool,
+    tunnel: Mutex<Option<Tunnel>>,
     shutdown_flag: Arc<AtomicBool>,
+    events: Option<EventSink>,
+    vpn_usage: Option<VpnUsage>,
 }
 
 impl ConnectionManager {
     /// Create a new connection manager for the given server.
     pub fn new(server: Server, settings: Settings, shutdown_flag: Arc<AtomicBool>) -> Self {
+        let vpn = vpn::backend_for(&server);
         Self {
             server,
             settings,
+            vpn,
             vpn_connected: AtomicBool::new(false),
+            tunnel: Mutex::new(None),
             shutdown_flag,
+            events: None,
+            vpn_usage: None,
+        }
+    }
+
+    /// Share the VPN with other managers using the same `usage` counts.
+    pub fn with_vpn_usage(mut self, usage: VpnUsage) -> Self {
+        self.vpn_usage = Some(usage);
+        self
+    }
+
+    /// Report progress events to `sink`.
+    pub fn with_events(mut self, sink: impl Fn(ConnectEvent) + Send + Sync + 'static) -> Self {
+        self.events = Some(Box::new(sink));
+        self
+    }
+
+    /// Send an event to the callback, if any.
+    fn emit(&self, event: ConnectEvent) {
+        if let Some(sink) = &self.events {
+            sink(event);
+        }
+    }
+
+    /// Whether shutdown (or cancellation) was requested.
+    pub fn is_shutdown(&self) -> bool {
+        self.shutdown_flag.load(Ordering::SeqCst)
+    }
+
+    /// Sleep for `duration`, waking early on shutdown.
+    ///
+    /// Returns false if shutdown was requested.
+    fn sleep(&self, duration: Duration) -> bool {
+        let deadline = Instant::now() + duration;
+        while !self.is_shutdown() {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return true;
+            }
+            std::thread::sleep(remaining.min(Duration::from_millis(100)));
         }
+        false
     }
 
     /// Connect to VPN and wait for it to establish.
-    pub fn connect_vpn(&self) -> Result<()> {
-        if self.shutdown_flag.load(Ordering::SeqCst) {
-            anyhow::bail!("Shutdown requested");
+    pub fn connect_vpn(&self) -> Result<(), ConnectError> {
+        if self.is_shutdown() {
+            return Err(ConnectError::Shutdown);
+        }
+
+        if !self.server.has_vpn() {
+            info!("No VPN configured for {}, skipping", self.server.name);
+            return Ok(());
         }
 
-        info!("Connecting to VPN: {}", self.server.vpn);
-        platform::connect_vpn(&self.server.vpn)?;
+        let mut first = false;
+        let mut connect = || -> Result<bool, ConnectError> {
+            first = true;
+            // Don't take over a tunnel the user brought up before sap_it
+            if self.vpn_already_active() {
+                info!(
+                    "VPN {} is already connected, leaving it as is",
+                    self.server.vpn
+                );
+                self.emit(ConnectEvent::VpnAlreadyUp {
+                    vpn: self.server.vpn.clone(),
+                });
+                return Ok(false);
+            }
+
+            info!(
+                "Connecting to VPN: {} (via {})",
+                self.server.vpn,
+                self.vpn.name()
+            );
+            self.emit(ConnectEvent::VpnConnecting {
+                vpn: self.server.vpn.clone(),
+                backend: self.vpn.name().to_string(),
+            });
+            self.vpn
+                .connect()
+                .map_err(|e| ConnectError::vpn(&self.server.vpn, self.vpn.name(), e))?;
+            Ok(true)
+        };
+
+        let owned = match &self.vpn_usage {
+            Some(usage) => usage.acquire(&self.server.vpn, connect)?,
+            None => connect()?,
+        };
+        if !owned {
+            return Ok(());
+        }
+        if !first {
+            info!("VPN {} is shared with another session", self.server.vpn);
+        }
         self.vpn_connected.store(true, Ordering::SeqCst);
 
         // Wait for VPN to establish with polling
@@ -71,8 +479,19 @@ impl ConnectionManager {
         Ok(())
     }
 
+    /// Whether the VPN is up already, treating query failures as down.
+    fn vpn_already_active(&self) -> bool {
+        match self.vpn.is_active() {
+            Ok(active) => active,
+            Err(e) => {
+                debug!("Could not query VPN state: {:#}", e);
+                false
+            }
+        }
+    }
+
     /// Wait for VPN connection to establish by polling connectivity.
-    fn wait_for_vpn_connection(&self) -> Result<()> {
+    fn wait_for_vpn_connection(&self) -> Result<(), ConnectError> {
         let timeout = Duration::from_secs(self.settings.vpn_timeout_secs);
         let start = Instant::now();
         let poll_interval = Duration::from_secs(2);
@@ -81,56 +500,101 @@ impl ConnectionManager {
             "Waiting for VPN connection (timeout: {}s)...",
             self.settings.vpn_timeout_secs
         );
+        self.emit(ConnectEvent::VpnWaiting);
 
         while start.elapsed() < timeout {
-            if self.shutdown_flag.load(Ordering::SeqCst) {
-                anyhow::bail!("Shutdown requested during VPN connection");
+            if self.is_shutdown() {
+                return Err(ConnectError::Shutdown);
             }
 
-            // Try to ping the RDP host to verify connectivity
-            debug!("Checking connectivity to {}...", self.server.rdp);
-            if platform::ping_host(&self.server.rdp, self.settings.ping_timeout_ms) {
+            // Probe the RDP (or jump) host to verify connectivity
+            let (host, port) = self.server.probe_target(&self.settings);
+            debug!("Checking connectivity to {}...", host);
+            if self.probe(host, port) {
                 info!("VPN connection established successfully");
+                self.emit(ConnectEvent::VpnUp);
                 return Ok(());
             }
 
             let remaining = timeout.saturating_sub(start.elapsed());
-            if remaining > poll_interval {
-                std::thread::sleep(poll_interval);
-            } else if remaining > Duration::ZERO {
-                std::thread::sleep(remaining);
+            if !self.sleep(remaining.min(poll_interval)) {
+                return Err(ConnectError::Shutdown);
             }
         }
 
         warn!("VPN connection timeout - proceeding anyway");
+        self.emit(ConnectEvent::VpnTimeout);
         Ok(())
     }
 
-    /// Disconnect from VPN.
+    /// Disconnect from VPN, unless other sessions still use it.
     pub fn disconnect_vpn(&self) {
-        if self.vpn_connected.load(Ordering::SeqCst) {
+        if !self.vpn_connected.swap(false, Ordering::SeqCst) {
+            return;
+        }
+
+        let disconnect = || {
             info!("Disconnecting VPN: {}", self.server.vpn);
-            if let Err(e) = platform::disconnect_vpn(&self.server.vpn) {
+            if let Err(e) = self.vpn.disconnect() {
                 error!("Failed to disconnect VPN: {}", e);
             }
-            self.vpn_connected.store(false, Ordering::SeqCst);
+        };
+
+        let last = match &self.vpn_usage {
+            Some(usage) => usage.release(&self.server.vpn, disconnect),
+            None => {
+                disconnect();
+                true
+            }
+        };
+        if !last {
+            info!("Keeping VPN {} up for other sessions", self.server.vpn);
         }
     }
 
+    /// Close the jump host tunnel if one is open.
+    pub fn close_tunnel(&self) {
+        if let Ok(mut tunnel) = self.tunnel.lock() {
+            if tunnel.take().is_some() {
+                info!("Closed SSH tunnel");
+            }
+        }
+    }
+
+    /// Probe a host once using the server's probe method.
+    fn probe(&self, host: &str, port: u16) -> bool {
+        probe_host(
+            host,
+            self.server.probe_method(&self.settings),
+            port,
+            &self.settings,
+        )
+    }
+
     /// Check if a host is reachable with retries.
-    pub fn check_host_reachable(&self, host: &str) -> bool {
+    pub fn check_host_reachable(&self, host: &str, port: u16) -> bool {
+        self.emit(ConnectEvent::Probing {
+            host: host.to_string(),
+        });
+
         for attempt in 1..=self.settings.ping_retries {
-            if self.shutdown_flag.load(Ordering::SeqCst) {
+            if self.is_shutdown() {
                 return false;
             }
 
             debug!(
-                "Ping attempt {} of {} for {}",
-                attempt, self.settings.ping_retries, host
+                "Probe attempt {} of {} for {} ({})",
+                attempt,
+                self.settings.ping_retries,
+                host,
+                self.server.probe_method(&self.settings).name()
             );
 
-            if platform::ping_host(host, self.settings.ping_timeout_ms) {
+            if self.probe(host, port) {
                 info!("Host {} is reachable", host);
+                self.emit(ConnectEvent::ProbeOk {
+                    host: host.to_string(),
+                });
                 return true;
             }
 
@@ -138,7 +602,9 @@ impl ConnectionManager {
                 // Exponential backoff: 1s, 2s, 4s...
                 let backoff = Duration::from_secs(1 << (attempt - 1));
                 debug!("Waiting {:?} before retry...", backoff);
-                std::thread::sleep(backoff);
+                if !self.sleep(backoff) {
+                    return false;
+                }
             }
         }
 
@@ -146,93 +612,170 @@ impl ConnectionManager {
             "Host {} is not reachable after {} attempts",
             host, self.settings.ping_retries
         );
+        self.emit(ConnectEvent::ProbeFailed {
+            host: host.to_string(),
+        });
         false
     }
 
+    /// Probe a host with retries, failing if it does not answer.
+    fn ensure_reachable(&self, host: &str, port: u16) -> Result<(), ConnectError> {
+        if self.check_host_reachable(host, port) {
+            Ok(())
+        } else if self.is_shutdown() {
+            Err(ConnectError::Shutdown)
+        } else {
+            Err(ConnectError::Unreachable {
+                host: host.to_string(),
+            })
+        }
+    }
+
     /// Start an RDP session and return the process handle.
-    pub fn start_rdp(&self) -> Result<Option<std::process::Child>> {
-        if self.shutdown_flag.load(Ordering::SeqCst) {
-            return Ok(None);
+    pub fn start_rdp(&self) -> Result<std::process::Child, ConnectError> {
+        if self.is_shutdown() {
+            return Err(ConnectError::Shutdown);
         }
 
-        if !self.check_host_reachable(&self.server.rdp) {
-            warn!(
-                "RDP host {} not reachable, skipping RDP session",
-                self.server.rdp
-            );
-            return Ok(None);
+        if let Some(jump) = self.server.jump_host() {
+            self.ensure_reachable(&jump.host, jump.port_or_default())?;
+
+            let (tunnel, rdp) =
+                tunnel_rdp(&self.server, jump).map_err(|e| ConnectError::client("ssh", e))?;
+            self.emit(ConnectEvent::TunnelOpen {
+                local_port: tunnel.local_port(),
+            });
+            if let Ok(mut slot) = self.tunnel.lock() {
+                *slot = Some(tunnel);
+            }
+
+            info!("Starting RDP session to {} via {}...", self.server.rdp, rdp);
+            let child = platform::start_rdp(&rdp, self.settings.rdp_client)
+                .map_err(|e| ConnectError::client(platform::RDP_CLIENT, e))?;
+            self.emit(ConnectEvent::SessionStarted {
+                session: ConnectionType::Rdp,
+            });
+            return Ok(child);
         }
 
+        self.ensure_reachable(
+            &self.server.rdp.host,
+            self.server.probe_port(&self.settings),
+        )?;
+
         info!("Starting RDP session to {}...", self.server.rdp);
-        let child = platform::start_rdp(&self.server.rdp).context("Failed to start RDP session")?;
+        let child = platform::start_rdp(&self.server.rdp, self.settings.rdp_client)
+            .map_err(|e| ConnectError::client(platform::RDP_CLIENT, e))?;
+        self.emit(ConnectEvent::SessionStarted {
+            session: ConnectionType::Rdp,
+        });
 
-        Ok(Some(child))
+        Ok(child)
     }
 
-    /// Start an SSH session (blocks until session ends).
-    pub fn start_ssh(&self) -> Result<()> {
-        if self.shutdown_flag.load(Ordering::SeqCst) {
-            return Ok(());
+    /// Start the RDP half of a `Both` connection.
+    ///
+    /// A failure is reported as `SessionSkipped` rather than returned, so the
+    /// SSH session still goes ahead; only shutdown is passed on.
+    pub fn start_rdp_or_skip(&self) -> Result<Option<std::process::Child>, ConnectError> {
+        match self.start_rdp() {
+            Ok(child) => Ok(Some(child)),
+            Err(ConnectError::Shutdown) => Err(ConnectError::Shutdown),
+            Err(e) => {
+                warn!("{}, skipping RDP session. {}", e, e.hint());
+                self.emit(ConnectEvent::SessionSkipped {
+                    session: ConnectionType::Rdp,
+                    error: e.to_string(),
+                    hint: e.hint(),
+                });
+                Ok(None)
+            }
         }
+    }
 
-        let ssh_string = self
+    /// Check that the SSH host (or its jump host) is reachable.
+    pub fn check_ssh_reachable(&self) -> Result<(), ConnectError> {
+        let ssh = self
             .server
-            .ssh_string()
-            .context("SSH not available for this server")?;
+            .ssh_config()
+            .ok_or_else(|| ConnectError::NoSsh {
+                server: self.server.name.clone(),
+            })?;
 
-        let ssh_ip = self
-            .server
-            .ssh_ip()
-            .context("Could not extract IP from SSH string")?;
+        // Behind a jump host only the bastion itself can be probed
+        let (probe_host, probe_port) = match self.server.jump_host() {
+            Some(jump) => (&jump.host, jump.port_or_default()),
+            None => (&ssh.host, ssh.port_or_default()),
+        };
 
-        if !self.check_host_reachable(&ssh_ip) {
-            warn!("SSH host {} not reachable, skipping SSH session", ssh_ip);
-            return Ok(());
+        self.ensure_reachable(probe_host, probe_port)
+    }
+
+    /// Start an SSH session (blocks until session ends).
+    pub fn start_ssh(&self) -> Result<(), ConnectError> {
+        if self.is_shutdown() {
+            return Err(ConnectError::Shutdown);
         }
 
-        info!("Starting SSH session to {}...", ssh_string);
-        platform::start_ssh(ssh_string).context("Failed to start SSH session")?;
+        self.check_ssh_reachable()?;
 
-        Ok(())
+        let ssh = self
+            .server
+            .ssh_config()
+            .ok_or_else(|| ConnectError::NoSsh {
+                server: self.server.name.clone(),
+            })?;
+        let jump = self.server.jump_host();
+
+        info!("Starting SSH session to {}...", ssh);
+        self.emit(ConnectEvent::SessionStarted {
+            session: ConnectionType::Ssh,
+        });
+        let (status, errors) =
+            platform::start_ssh(ssh, jump).map_err(|e| ConnectError::client("ssh", e))?;
+
+        check_ssh_exit(ssh, status.code(), &errors)
     }
 
     /// Execute the connection based on the selected type.
-    pub fn connect(&self, conn_type: ConnectionType) -> Result<()> {
+    pub fn connect(&self, conn_type: ConnectionType) -> Result<(), ConnectError> {
         // Connect to VPN first
         self.connect_vpn()?;
 
         match conn_type {
             ConnectionType::Rdp => {
-                if let Some(mut child) = self.start_rdp()? {
-                    info!("Waiting for RDP session to end...");
-                    let _ = child.wait();
-                }
+                let mut child = self.start_rdp()?;
+                info!("Waiting for RDP session to end...");
+                let _ = child.wait();
             }
             ConnectionType::Ssh => {
                 self.start_ssh()?;
             }
             ConnectionType::Both => {
-                // Start RDP first (non-blocking)
-                let rdp_child = self.start_rdp()?;
+                // Start RDP first (non-blocking), going on without it
+                let rdp_child = self.start_rdp_or_skip()?;
 
                 // Then start SSH (blocking)
-                self.start_ssh()?;
+                let ssh = self.start_ssh();
 
-                // Wait for RDP to finish if it was started
+                // Wait for RDP to finish even if SSH failed
                 if let Some(mut child) = rdp_child {
                     info!("Waiting for RDP session to end...");
                     let _ = child.wait();
                 }
+                ssh?;
             }
         }
 
+        self.emit(ConnectEvent::SessionEnded);
         Ok(())
     }
 }
 
 impl Drop for ConnectionManager {
     fn drop(&mut self) {
-        // Ensure VPN is disconnected when the manager is dropped
+        // Ensure the tunnel and VPN are torn down when the manager is dropped
+        self.close_tunnel();
         self.disconnect_vpn();
     }
 }
@@ -240,6 +783,9 @@ impl Drop for ConnectionManager {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashSet;
+    use std::net::TcpListener;
+    use std::process::Command;
 
     #[test]
     fn test_connection_type_names() {
@@ -256,4 +802,258 @@ mod tests {
         assert!(all.contains(&ConnectionType::Ssh));
         assert!(all.contains(&ConnectionType::Both));
     }
+
+    #[test]
+    fn test_vpn_usage_counts() {
+        let usage = VpnUsage::default();
+        // Returns whether `connect` ran, i.e. the session was the first one
+        let acquire = |vpn: &str| {
+            let mut first = false;
+            let counted = usage.acquire(vpn, || {
+                first = true;
+                Ok::<_, ()>(true)
+            });
+            assert_eq!(counted, Ok(true));
+            first
+        };
+        assert!(acquire("Industrial Technik"));
+        assert!(!acquire("Industrial Technik"));
+        assert!(acquire("ILMATEX"));
+        assert_eq!(usage.count("Industrial Technik"), 2);
+
+        assert!(!usage.release("Industrial Technik", || panic!("still in use")));
+        let mut disconnected = false;
+        assert!(usage.release("Industrial Technik", || disconnected = true));
+        assert!(disconnected);
+        assert_eq!(usage.count("Industrial Technik"), 0);
+        assert_eq!(usage.count("ILMATEX"), 1);
+
+        // A VPN that was up before isn't counted
+        assert_eq!(usage.acquire("Nova", || Ok::<_, ()>(false)), Ok(false));
+        assert_eq!(usage.count("Nova"), 0);
+    }
+
+    #[test]
+    fn test_failed_vpn_connect_is_not_shared() {
+        let usage = VpnUsage::default();
+        let (started, start) = std::sync::mpsc::channel();
+
+        // The first session fails to connect while a second one waits
+        let first = {
+            let usage = usage.clone();
+            std::thread::spawn(move || {
+                usage.acquire("ILMATEX", || {
+                    started.send(()).unwrap();
+                    std::thread::sleep(Duration::from_millis(200));
+                    Err("auth failed")
+                })
+            })
+        };
+        start.recv().unwrap();
+
+        let mut connected = false;
+        let counted = usage.acquire("ILMATEX", || {
+            connected = true;
+            Ok::<_, &str>(true)
+        });
+        assert_eq!(first.join().unwrap(), Err("auth failed"));
+
+        // The second session had to bring the VPN up itself
+        assert_eq!(counted, Ok(true));
+        assert!(connected);
+        assert_eq!(usage.count("ILMATEX"), 1);
+    }
+
+    #[test]
+    fn test_connect_event_json() {
+        let event = ConnectEvent::VpnConnecting {
+            vpn: "ILMATEX".to_string(),
+            backend: "nmcli".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"vpn_connecting","vpn":"ILMATEX","backend":"nmcli"}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&ConnectEvent::VpnUp).unwrap(),
+            r#"{"event":"vpn_up"}"#
+        );
+        let event = ConnectEvent::SessionStarted {
+            session: ConnectionType::Rdp,
+        };
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"session_started","session":"rdp"}"#
+        );
+    }
+
+    #[test]
+    fn test_connect_error_exit_codes() {
+        let errors = [
+            ConnectError::Vpn(VpnError::VpnNotFound {
+                vpn: "ILMATEX".to_string(),
+            }),
+            ConnectError::Unreachable {
+                host: "10.0.0.1".to_string(),
+            },
+            ConnectError::ClientMissing { client: "ssh" },
+            ConnectError::Spawn {
+                client: "ssh",
+                message: "exited".to_string(),
+            },
+            ConnectError::Auth {
+                target: "VPN 'ILMATEX'".to_string(),
+            },
+            ConnectError::NoSsh {
+                server: "ALPI".to_string(),
+            },
+            ConnectError::Shutdown,
+        ];
+        let codes: HashSet<i32> = errors.iter().map(ConnectError::exit_code).collect();
+        assert_eq!(codes.len(), errors.len());
+        assert!(!codes.contains(&0) && !codes.contains(&1) && !codes.contains(&2));
+    }
+
+    #[test]
+    fn test_connect_error_classification() {
+        assert_eq!(
+            ConnectError::from(VpnError::BackendMissing { backend: "nmcli" }),
+            ConnectError::ClientMissing { client: "nmcli" }
+        );
+        assert_eq!(
+            ConnectError::from(VpnError::AuthFailed {
+                vpn: "ILMATEX".to_string()
+            }),
+            ConnectError::Auth {
+                target: "VPN 'ILMATEX'".to_string()
+            }
+        );
+
+        let missing = anyhow::Error::new(io::Error::from(io::ErrorKind::NotFound))
+            .context("Failed to start RDP client");
+        assert_eq!(
+            ConnectError::client("rdesktop", missing),
+            ConnectError::ClientMissing { client: "rdesktop" }
+        );
+
+        let failed = anyhow::anyhow!("openvpn exited with status 1");
+        assert_eq!(
+            ConnectError::vpn("ILMATEX", "openvpn", failed),
+            ConnectError::Vpn(VpnError::Failed {
+                vpn: "ILMATEX".to_string(),
+                message: "openvpn exited with status 1".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_ssh_exit_status() {
+        let target: SshConfig = "root@10.0.0.1".parse().unwrap();
+        // Other codes come from the remote shell, whatever it printed
+        assert!(check_ssh_exit(&target, Some(0), "").is_ok());
+        assert!(check_ssh_exit(&target, Some(1), "Permission denied").is_ok());
+
+        assert_eq!(
+            check_ssh_exit(
+                &target,
+                Some(255),
+                "root@10.0.0.1: Permission denied (publickey,password).\n"
+            ),
+            Err(ConnectError::Auth {
+                target: "SSH root@10.0.0.1".to_string()
+            })
+        );
+        assert_eq!(
+            check_ssh_exit(
+                &target,
+                Some(255),
+                "ssh: connect to host 10.0.0.1 port 22: Connection refused\r\n"
+            ),
+            Err(ConnectError::Unreachable {
+                host: "10.0.0.1".to_string()
+            })
+        );
+        assert_eq!(
+            check_ssh_exit(
+                &target,
+                Some(255),
+                "@@@@@@@@\nIT IS POSSIBLE THAT SOMEONE IS DOING SOMETHING NASTY!\n\
+                 Host key verification failed.\n"
+            ),
+            Err(ConnectError::Spawn {
+                client: "ssh",
+                message: "Host key verification failed.".to_string()
+            })
+        );
+        assert_eq!(
+            check_ssh_exit(&target, Some(255), ""),
+            Err(ConnectError::Spawn {
+                client: "ssh",
+                message: "ssh exited with status 255".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_both_goes_on_without_rdp() {
+        if Command::new("ssh").arg("-V").output().is_err() {
+            return; // ssh not installed
+        }
+        // Bind then drop to get a port nobody listens on
+        let rdp_port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        // An SSH "server" that hangs up right away, so ssh exits at once
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let ssh_port = listener.local_addr().unwrap().port();
+        std::thread::spawn(move || for _ in listener.incoming() {});
+
+        let server = Server {
+            name: "NoRdp".to_string(),
+            rdp: format!("127.0.0.1:{}", rdp_port).parse().unwrap(),
+            ssh: Some(
+                format!("root@127.0.0.1:{}", ssh_port)
+                    .parse::<SshConfig>()
+                    .map(|mut ssh| {
+                        ssh.options = vec!["BatchMode=yes".to_string()];
+                        ssh
+                    })
+                    .unwrap(),
+            ),
+            probe_method: Some(ProbeMethod::Tcp),
+            ..Default::default()
+        };
+        let settings = Settings {
+            ping_retries: 1,
+            ..Default::default()
+        };
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let manager = ConnectionManager::new(server, settings, Arc::new(AtomicBool::new(false)))
+            .with_events(move |event| sink.lock().unwrap().push(event));
+        let result = manager.connect(ConnectionType::Both);
+
+        // The hangup before the handshake is ssh's error, not a rejected login
+        match result {
+            Err(ConnectError::Spawn {
+                client: "ssh",
+                message,
+            }) => assert!(message.contains("127.0.0.1"), "{}", message),
+            other => panic!("expected an ssh error, got {:?}", other),
+        }
+        let events = events.lock().unwrap();
+        assert!(events.iter().any(|e| matches!(
+            e,
+            ConnectEvent::SessionSkipped {
+                session: ConnectionType::Rdp,
+                ..
+            }
+        )));
+        assert!(events.contains(&ConnectEvent::SessionStarted {
+            session: ConnectionType::Ssh
+        }));
+    }
 }
@@ -0,0 +1,387 @@
+//! Environment diagnostics for `sap_it doctor`.
+//!
+//! Checks that the client programs sap_it runs are installed, that the
+//! configuration file loads, and that the servers in it are consistent with
+//! each other and with NetworkManager, so a new setup can be verified in one go.
+
+use crate::config::{Config, ProbeMethod, Server, Settings};
+use crate::platform::{self, vpn};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+/// RDP clients `platform::start_rdp` tries, in order.
+#[cfg(windows)]
+const RDP_CLIENTS: &[&str] = &["mstsc"];
+
+#[cfg(not(windows))]
+const RDP_CLIENTS: &[&str] = &["xfreerdp", "xfreerdp3", "rdesktop"];
+
+/// Other programs every setup needs; `ping` depends on the probe method.
+const REQUIRED_TOOLS: &[&str] = &["ssh"];
+
+/// Outcome of a single check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+/// One line of the report.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Check {
+    /// Section the check belongs to (binaries, config, vpn, servers).
+    pub category: &'static str,
+    /// What was checked.
+    pub name: String,
+    /// Outcome.
+    pub status: CheckStatus,
+    /// Found path, error message or other explanation.
+    pub detail: String,
+}
+
+/// Results of all checks, in the order they ran.
+#[derive(Debug, Default, Serialize)]
+pub struct Report {
+    pub checks: Vec<Check>,
+}
+
+impl Report {
+    /// Record a check result.
+    fn push(
+        &mut self,
+        category: &'static str,
+        name: impl Into<String>,
+        status: CheckStatus,
+        detail: impl Into<String>,
+    ) {
+        self.checks.push(Check {
+            category,
+            name: name.into(),
+            status,
+            detail: detail.into(),
+        });
+    }
+
+    /// Number of checks with the given status.
+    pub fn count(&self, status: CheckStatus) -> usize {
+        self.checks.iter().filter(|c| c.status == status).count()
+    }
+}
+
+/// Run every check against the configuration file at `config_path`.
+///
+/// Server checks are skipped when the file is missing, since the built-in
+/// defaults only contain example servers.
+pub fn run(config_path: &Path) -> Report {
+    let mut report = Report::default();
+    // The config tells whether ping is needed, but is reported after the binaries
+    let mut config_checks = Report::default();
+    let config = check_config(&mut config_checks, config_path);
+
+    check_binaries(&mut report, config.as_ref());
+    report.checks.append(&mut config_checks.checks);
+    if let Some(config) = config {
+        check_vpns(&mut report, &config);
+        check_duplicates(&mut report, &config);
+    }
+    report
+}
+
+/// Check that an RDP client and the other required programs are installed.
+///
+/// `config` is None when the built-in defaults are used.
+fn check_binaries(report: &mut Report, config: Option<&Config>) {
+    let rdp: Vec<_> = RDP_CLIENTS
+        .iter()
+        .map(|&name| (name, platform::find_executable(name)))
+        .collect();
+    let any_rdp = rdp.iter().any(|(_, path)| path.is_some());
+
+    for (name, path) in rdp {
+        match path {
+            Some(path) => report.push(
+                "binaries",
+                name,
+                CheckStatus::Pass,
+                path.display().to_string(),
+            ),
+            // One RDP client is enough
+            None if any_rdp => report.push("binaries", name, CheckStatus::Warn, "not installed"),
+            None => report.push(
+                "binaries",
+                name,
+                CheckStatus::Fail,
+                "not installed, no RDP client available",
+            ),
+        }
+    }
+
+    for &name in REQUIRED_TOOLS {
+        match platform::find_executable(name) {
+            Some(path) => report.push(
+                "binaries",
+                name,
+                CheckStatus::Pass,
+                path.display().to_string(),
+            ),
+            None => report.push("binaries", name, CheckStatus::Fail, "not installed"),
+        }
+    }
+
+    match platform::find_executable("ping") {
+        Some(path) => report.push(
+            "binaries",
+            "ping",
+            CheckStatus::Pass,
+            path.display().to_string(),
+        ),
+        None => {
+            let (status, detail) = missing_ping(config);
+            report.push("binaries", "ping", status, detail);
+        }
+    }
+}
+
+/// How much a missing `ping` matters: only the icmp probe can't do without
+/// it, the both probe falls back to tcp.
+fn missing_ping(config: Option<&Config>) -> (CheckStatus, &'static str) {
+    let methods: Vec<ProbeMethod> = match config {
+        Some(config) => config
+            .servers
+            .iter()
+            .map(|server| server.probe_method(&config.settings))
+            .collect(),
+        None => vec![Settings::default().probe_method],
+    };
+
+    if methods.contains(&ProbeMethod::Icmp) {
+        (
+            CheckStatus::Fail,
+            "not installed, needed by probe_method \"icmp\"",
+        )
+    } else if methods.contains(&ProbeMethod::Both) {
+        (
+            CheckStatus::Warn,
+            "not installed, probe_method \"both\" only uses tcp",
+        )
+    } else {
+        (
+            CheckStatus::Pass,
+            "not installed, not needed with probe_method \"tcp\"",
+        )
+    }
+}
+
+/// Check that the configuration file exists and parses.
+fn check_config(report: &mut Report, path: &Path) -> Option<Config> {
+    let name = path.display().to_string();
+    if !path.exists() {
+        report.push(
+            "config",
+            name,
+            CheckStatus::Warn,
+            "not found, built-in defaults are used (run 'sap_it init')",
+        );
+        return None;
+    }
+
+    match Config::load(&path.to_path_buf()) {
+        Ok(config) => {
+            report.push(
+                "config",
+                name,
+                CheckStatus::Pass,
+                format!("{} servers", config.servers.len()),
+            );
+            Some(config)
+        }
+        Err(e) => {
+            report.push("config", name, CheckStatus::Fail, format!("{:#}", e));
+            None
+        }
+    }
+}
+
+/// Check that the VPN clients are installed and the NetworkManager
+/// connections exist.
+fn check_vpns(report: &mut Report, config: &Config) {
+    let mut backends: BTreeMap<&'static str, usize> = BTreeMap::new();
+    let mut nm_connections = BTreeSet::new();
+    for server in config.servers.iter().filter(|s| s.has_vpn()) {
+        let backend = vpn::backend_for(server).name();
+        *backends.entry(backend).or_default() += 1;
+        if backend == "nmcli" {
+            nm_connections.insert(server.vpn.as_str());
+        }
+    }
+
+    for (&backend, &count) in &backends {
+        match platform::find_executable(backend) {
+            Some(path) => report.push(
+                "vpn",
+                backend,
+                CheckStatus::Pass,
+                path.display().to_string(),
+            ),
+            None => report.push(
+                "vpn",
+                backend,
+                CheckStatus::Fail,
+                format!("not installed, needed by {} server(s)", count),
+            ),
+        }
+    }
+
+    if nm_connections.is_empty() || platform::find_executable("nmcli").is_none() {
+        return;
+    }
+
+    let known = match vpn::nmcli_connections(false) {
+        Ok(known) => known,
+        Err(e) => {
+            report.push(
+                "vpn",
+                "NetworkManager connections",
+                CheckStatus::Warn,
+                format!("could not be listed: {:#}", e),
+            );
+            return;
+        }
+    };
+
+    for name in nm_connections {
+        if known.iter().any(|k| k == name) {
+            report.push("vpn", name, CheckStatus::Pass, "found in NetworkManager");
+        } else {
+            report.push(
+                "vpn",
+                name,
+                CheckStatus::Fail,
+                "no NetworkManager connection with this name",
+            );
+        }
+    }
+}
+
+/// Flag servers sharing a name (ambiguous for `connect`) or an address.
+fn check_duplicates(report: &mut Report, config: &Config) {
+    let mut names: BTreeMap<String, Vec<&Server>> = BTreeMap::new();
+    let mut addresses: BTreeMap<(String, String, u16), Vec<&Server>> = BTreeMap::new();
+    for server in &config.servers {
+        names
+            .entry(server.name.to_lowercase())
+            .or_default()
+            .push(server);
+        // The same private address behind different jump hosts is fine
+        let jump = server
+            .jump_host()
+            .map(|j| j.host.to_lowercase())
+            .unwrap_or_default();
+        addresses
+            .entry((
+                jump,
+                server.rdp.host.to_lowercase(),
+                server.rdp.port_or_default(),
+            ))
+            .or_default()
+            .push(server);
+    }
+
+    let mut clean = true;
+    for servers in names.values().filter(|s| s.len() > 1) {
+        clean = false;
+        report.push(
+            "servers",
+            servers[0].name.clone(),
+            CheckStatus::Fail,
+            format!("name is used by {} servers", servers.len()),
+        );
+    }
+    for servers in addresses.values().filter(|s| s.len() > 1) {
+        clean = false;
+        let list: Vec<&str> = servers.iter().map(|s| s.name.as_str()).collect();
+        report.push(
+            "servers",
+            servers[0].rdp.to_string(),
+            CheckStatus::Warn,
+            format!("address is shared by {}", list.join(", ")),
+        );
+    }
+
+    if clean {
+        report.push(
+            "servers",
+            "duplicates",
+            CheckStatus::Pass,
+            "no duplicate names or addresses",
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(name: &str, rdp: &str) -> Server {
+        Server {
+            name: name.to_string(),
+            rdp: rdp.parse().unwrap(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_duplicates_are_flagged() {
+        let mut config = Config::default_config();
+        config.servers = vec![
+            server("ALPI", "10.0.0.1"),
+            server("alpi", "10.0.0.2"),
+            server("Nova", "10.0.0.2:3389"),
+        ];
+
+        let mut report = Report::default();
+        check_duplicates(&mut report, &config);
+        assert_eq!(report.count(CheckStatus::Fail), 1);
+        assert_eq!(report.count(CheckStatus::Warn), 1);
+        assert_eq!(report.count(CheckStatus::Pass), 0);
+
+        config.servers[1].name = "Ilmatex".to_string();
+        config.servers[2].rdp = "10.0.0.2:3390".parse().unwrap();
+        let mut report = Report::default();
+        check_duplicates(&mut report, &config);
+        assert_eq!(report.count(CheckStatus::Pass), 1);
+    }
+
+    #[test]
+    fn test_ping_is_only_required_for_icmp() {
+        let mut config = Config::default_config();
+        config.settings.probe_method = ProbeMethod::Tcp;
+        assert_eq!(missing_ping(Some(&config)).0, CheckStatus::Pass);
+
+        config.servers[0].probe_method = Some(ProbeMethod::Both);
+        assert_eq!(missing_ping(Some(&config)).0, CheckStatus::Warn);
+
+        config.servers[1].probe_method = Some(ProbeMethod::Icmp);
+        assert_eq!(missing_ping(Some(&config)).0, CheckStatus::Fail);
+
+        // The built-in defaults probe with icmp
+        assert_eq!(missing_ping(None).0, CheckStatus::Fail);
+    }
+
+    #[test]
+    fn test_missing_config_skips_server_checks() {
+        let dir = tempfile::tempdir().unwrap();
+        let report = run(&dir.path().join("servers.toml"));
+
+        let config: Vec<&Check> = report
+            .checks
+            .iter()
+            .filter(|c| c.category != "binaries")
+            .collect();
+        assert_eq!(config.len(), 1);
+        assert_eq!(config[0].status, CheckStatus::Warn);
+    }
+}
@@ -4,27 +4,38 @@
 //! via VPN, RDP, and SSH.
 
 mod config;
+mod config_file;
 mod connection;
+mod doctor;
 mod platform;
 mod tui;
 mod ui;
+mod validate;
 
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use config::Config;
-use connection::{ConnectionManager, ConnectionType};
+use config_file::ConfigFile;
+use connection::{check_ssh_exit, ConnectError, ConnectionManager, ConnectionType};
 use crossterm::{
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
+use serde::Serialize;
 use std::io::stdout;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use tracing::{debug, info, Level};
 use tracing_subscriber::EnvFilter;
 
+/// TUI tick rate in milliseconds.
+const TICK_RATE_MS: u64 = 250;
+
+/// TUI tick rate while an embedded SSH terminal is open.
+const PTY_TICK_RATE_MS: u64 = 30;
+
 /// SAP-IT Server Connection Manager
 #[derive(Parser, Debug)]
 #[command(name = "sap_it")]
@@ -48,6 +59,139 @@ struct Cli {
     command: Option<Commands>,
 }
 
+/// Output format of the `list` subcommand.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum ListFormat {
+    /// Colored text for humans
+    Table,
+    Json,
+    Yaml,
+    Csv,
+}
+
+/// Changes made by the `server` subcommand.
+#[derive(Subcommand, Debug)]
+enum ServerAction {
+    /// Add a server
+    Add {
+        /// Server name
+        name: String,
+
+        #[command(flatten)]
+        fields: ServerFields,
+    },
+
+    /// Change fields of a server
+    Edit {
+        /// Server name or index (1-based)
+        server: String,
+
+        #[command(flatten)]
+        fields: ServerFields,
+    },
+
+    /// Remove a server
+    Rm {
+        /// Server name or index (1-based)
+        server: String,
+    },
+
+    /// Rename a server
+    Mv {
+        /// Server name or index (1-based)
+        server: String,
+
+        /// New name
+        new_name: String,
+    },
+}
+
+/// Server fields settable from the command line; an empty value removes
+/// optional fields.
+#[derive(Args, Debug)]
+struct ServerFields {
+    /// RDP address ("host" or "host:port")
+    #[arg(long)]
+    rdp: Option<String>,
+
+    /// SSH target ("user@host[:port]")
+    #[arg(long)]
+    ssh: Option<String>,
+
+    /// VPN connection name
+    #[arg(long)]
+    vpn: Option<String>,
+
+    /// SSH jump host ("user@host[:port]")
+    #[arg(long)]
+    jump: Option<String>,
+
+    /// Group the server is listed under
+    #[arg(long)]
+    group: Option<String>,
+
+    /// Comma-separated tags, replacing the current ones
+    #[arg(long, value_delimiter = ',')]
+    tags: Option<Vec<String>>,
+}
+
+impl ServerFields {
+    /// Apply the given fields to `server`.
+    ///
+    /// Like the TUI form, address changes keep the table-only options
+    /// (RDP client options, SSH identity file and options).
+    fn apply(self, server: &mut config::Server) -> Result<()> {
+        if let Some(rdp) = self.rdp {
+            let address: config::RdpConfig = rdp
+                .parse()
+                .map_err(|e| anyhow::anyhow!("Invalid RDP address: {}", e))?;
+            server.rdp.host = address.host;
+            server.rdp.port = address.port;
+        }
+        if let Some(ssh) = self.ssh {
+            server.ssh = parse_ssh(&ssh, server.ssh.take(), "SSH target")?;
+        }
+        if let Some(jump) = self.jump {
+            server.jump = parse_ssh(&jump, server.jump.take(), "jump host")?;
+        }
+        if let Some(vpn) = self.vpn {
+            server.vpn = vpn.trim().to_string();
+        }
+        if let Some(group) = self.group {
+            let group = group.trim();
+            server.group = (!group.is_empty()).then(|| group.to_string());
+        }
+        if let Some(tags) = self.tags {
+            server.tags = tags
+                .iter()
+                .map(|tag| tag.trim())
+                .filter(|tag| !tag.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+        Ok(())
+    }
+}
+
+/// Parse an SSH target, keeping options of the previous one; empty removes it.
+fn parse_ssh(
+    value: &str,
+    previous: Option<config::SshConfig>,
+    what: &str,
+) -> Result<Option<config::SshConfig>> {
+    if value.trim().is_empty() {
+        return Ok(None);
+    }
+    let mut ssh: config::SshConfig = value
+        .parse()
+        .map_err(|e| anyhow::anyhow!("Invalid {}: {}", what, e))?;
+    if let Some(previous) = previous {
+        ssh.identity_file = previous.identity_file;
+        ssh.options = previous.options;
+    }
+    Ok(Some(ssh))
+}
+
 #[derive(Subcommand, Debug)]
 enum Commands {
     /// Generate a sample configuration file
@@ -58,7 +202,19 @@ enum Commands {
     },
 
     /// List all configured servers
-    List,
+    List {
+        /// Only show servers in this group
+        #[arg(short, long)]
+        group: Option<String>,
+
+        /// Only show servers with this tag (can be repeated)
+        #[arg(short, long = "tag", value_name = "TAG")]
+        tag: Vec<String>,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = ListFormat::Table)]
+        format: ListFormat,
+    },
 
     /// Connect to a server directly by name or index
     Connect {
@@ -68,12 +224,43 @@ enum Commands {
         /// Connection type: rdp, ssh, or both
         #[arg(short = 't', long, default_value = "rdp")]
         connection_type: String,
+
+        /// Print progress as JSON events, one per line
+        ///
+        /// An interactive SSH session (-t ssh or both) writes to the same
+        /// stdout between the events.
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Add, edit, remove or rename servers in the configuration file
+    Server {
+        #[command(subcommand)]
+        action: ServerAction,
+    },
+
+    /// Check the configuration file for mistakes
+    Validate {
+        /// File to check (defaults to --config or the default location)
+        file: Option<PathBuf>,
+    },
+
+    /// Check the environment and configuration for common problems
+    Doctor {
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
     },
 }
 
 fn main() {
     if let Err(e) = run() {
         ui::error(&format!("{:#}", e));
+        // Connection failures get a hint and their own exit code for scripts
+        if let Some(connect) = e.downcast_ref::<ConnectError>() {
+            ui::error_hint(&connect.hint());
+            std::process::exit(connect.exit_code());
+        }
         std::process::exit(1);
     }
 }
@@ -91,16 +278,29 @@ fn run() -> Result<()> {
     // Handle subcommands
     match cli.command {
         Some(Commands::Init { output }) => init_config(&output),
-        Some(Commands::List) => {
-            let config = load_config(cli.config.as_ref(), true)?;
-            list_servers(&config)
+        Some(Commands::List { group, tag, format }) => {
+            let config = load_config(cli.config.as_ref(), format == ListFormat::Table)?;
+            match format {
+                ListFormat::Table => list_servers(&config, group.as_deref(), &tag),
+                _ => export_servers(&config, group.as_deref(), &tag, format),
+            }
         }
         Some(Commands::Connect {
             server,
             connection_type,
-        }) => {
-            let config = load_config(cli.config.as_ref(), true)?;
-            direct_connect(&config, &server, &connection_type)
+            json,
+        }) => direct_connect(cli.config.as_ref(), &server, &connection_type, json),
+        Some(Commands::Server { action }) => {
+            let path = cli.config.clone().unwrap_or_else(Config::default_path);
+            edit_servers(&path, action)
+        }
+        Some(Commands::Validate { file }) => {
+            let path = file.or(cli.config).unwrap_or_else(Config::default_path);
+            validate_config(&path)
+        }
+        Some(Commands::Doctor { json }) => {
+            let config_path = cli.config.clone().unwrap_or_else(Config::default_path);
+            run_doctor(&config_path, json)
         }
         None => {
             // Interactive mode
@@ -124,9 +324,11 @@ fn init_logging(verbosity: u8) {
 
     let filter = EnvFilter::from_default_env().add_directive(level.into());
 
+    // Logs go to stderr so they don't mix with JSON/CSV output
     tracing_subscriber::fmt()
         .with_env_filter(filter)
         .with_target(false)
+        .with_writer(std::io::stderr)
         .init();
 }
 
@@ -170,41 +372,232 @@ fn init_config(output: &PathBuf) -> Result<()> {
     Ok(())
 }
 
-/// List all configured servers.
-fn list_servers(config: &Config) -> Result<()> {
+/// List configured servers, optionally filtered by group and tags.
+fn list_servers(config: &Config, group: Option<&str>, tags: &[String]) -> Result<()> {
     ui::display_header();
     println!("{}", "Configured Servers:".cyan());
     println!("{}", "─".repeat(40));
 
-    for (i, server) in config.servers.iter().enumerate() {
-        let ssh_status = if server.has_ssh() {
-            "SSH available".green()
-        } else {
-            "RDP only".yellow()
-        };
+    let show_groups = config.servers.iter().any(|s| s.group_name().is_some());
+    let mut shown = 0;
+
+    for (group_name, indices) in config.grouped_servers() {
+        let matching: Vec<usize> = indices
+            .into_iter()
+            .filter(|&i| config.servers[i].matches_filter(group, tags))
+            .collect();
+        if matching.is_empty() {
+            continue;
+        }
+
+        if show_groups {
+            println!();
+            println!("{}", group_name.unwrap_or("Ungrouped").cyan().bold());
+        }
+
+        for i in matching {
+            print_server(i, &config.servers[i]);
+            shown += 1;
+        }
+    }
 
+    if shown == 0 {
         println!();
+        ui::warning("No servers match the given filter");
+    }
+
+    println!();
+    Ok(())
+}
+
+/// One server in machine-readable `list` output.
+#[derive(Serialize)]
+struct ServerSummary<'a> {
+    /// 1-based index, as accepted by `connect`.
+    index: usize,
+    name: &'a str,
+    group: Option<&'a str>,
+    vpn: Option<&'a str>,
+    rdp: String,
+    ssh: Option<String>,
+    jump: Option<String>,
+    tags: &'a [String],
+}
+
+impl<'a> ServerSummary<'a> {
+    fn new(index: usize, server: &'a config::Server) -> Self {
+        Self {
+            index: index + 1,
+            name: &server.name,
+            group: server.group_name(),
+            vpn: server.has_vpn().then_some(server.vpn.as_str()),
+            rdp: server.rdp.to_string(),
+            ssh: server.ssh_string(),
+            jump: server.jump_host().map(|j| j.to_string()),
+            tags: &server.tags,
+        }
+    }
+}
+
+/// Print servers matching the filter as JSON, YAML or CSV, in config order.
+fn export_servers(
+    config: &Config,
+    group: Option<&str>,
+    tags: &[String],
+    format: ListFormat,
+) -> Result<()> {
+    let servers: Vec<ServerSummary> = config
+        .servers
+        .iter()
+        .enumerate()
+        .filter(|(_, server)| server.matches_filter(group, tags))
+        .map(|(i, server)| ServerSummary::new(i, server))
+        .collect();
+
+    match format {
+        ListFormat::Json => {
+            let output =
+                serde_json::to_string_pretty(&servers).context("Failed to serialize servers")?;
+            println!("{}", output);
+        }
+        ListFormat::Yaml => {
+            let output = serde_yaml::to_string(&servers).context("Failed to serialize servers")?;
+            print!("{}", output);
+        }
+        ListFormat::Csv => {
+            // Tags are joined, CSV has no lists
+            let mut writer = csv::Writer::from_writer(stdout());
+            writer.write_record([
+                "index", "name", "group", "vpn", "rdp", "ssh", "jump", "tags",
+            ])?;
+            for server in &servers {
+                writer.write_record([
+                    server.index.to_string().as_str(),
+                    server.name,
+                    server.group.unwrap_or(""),
+                    server.vpn.unwrap_or(""),
+                    &server.rdp,
+                    server.ssh.as_deref().unwrap_or(""),
+                    server.jump.as_deref().unwrap_or(""),
+                    &server.tags.join(";"),
+                ])?;
+            }
+            writer.flush()?;
+        }
+        ListFormat::Table => list_servers(config, group, tags)?,
+    }
+
+    Ok(())
+}
+
+/// Print one server entry of the `list` output.
+fn print_server(index: usize, server: &config::Server) {
+    let ssh_status = if server.has_ssh() {
+        "SSH available".green()
+    } else {
+        "RDP only".yellow()
+    };
+
+    println!();
+    println!(
+        "  {}. {} ({})",
+        index + 1,
+        server.name.white().bold(),
+        ssh_status
+    );
+    println!(
+        "     VPN: {}",
+        if server.has_vpn() {
+            &server.vpn
+        } else {
+            "None"
+        }
+    );
+    if let Some(jump) = server.jump_host() {
+        println!("     Jump: {}", jump);
+    }
+    println!("     RDP: {}", server.rdp);
+    if let Some(ssh) = server.ssh_string() {
+        println!("     SSH: {}", ssh);
+    }
+    if !server.tags.is_empty() {
+        println!("     Tags: {}", server.tags.join(", "));
+    }
+}
+
+/// Validate a configuration file and print every problem with its location.
+fn validate_config(path: &Path) -> Result<()> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+
+    let issues = validate::validate(&content);
+    if issues.is_empty() {
+        ui::success(&format!("{} is valid", path.display()));
+        return Ok(());
+    }
+
+    for issue in &issues {
         println!(
-            "  {}. {} ({})",
-            i + 1,
-            server.name.white().bold(),
-            ssh_status
+            "{} {}:{}:{}: {}",
+            "✗".red(),
+            path.display(),
+            issue.line,
+            issue.column,
+            issue.message
         );
-        println!("     VPN: {}", server.vpn);
-        println!("     RDP: {}", server.rdp);
-        if let Some(ssh) = server.ssh_string() {
-            println!("     SSH: {}", ssh);
+        if let Some(line) = content.lines().nth(issue.line - 1) {
+            println!("    {}", line.dimmed());
+            println!("    {}{}", " ".repeat(issue.column - 1), "^".red());
         }
     }
-
     println!();
+    anyhow::bail!("{} problem(s) found in {}", issues.len(), path.display())
+}
+
+/// Run the environment checks and print the report.
+///
+/// Fails if any check failed, so scripts can test the exit code.
+fn run_doctor(config_path: &Path, json: bool) -> Result<()> {
+    let report = doctor::run(config_path);
+
+    if json {
+        let output = serde_json::to_string_pretty(&report).context("Failed to serialize report")?;
+        println!("{}", output);
+    } else {
+        ui::display_header();
+        let mut category = "";
+        for check in &report.checks {
+            if check.category != category {
+                category = check.category;
+                println!();
+                println!("{}", category.to_uppercase().cyan().bold());
+            }
+            let symbol = match check.status {
+                doctor::CheckStatus::Pass => "✓".green(),
+                doctor::CheckStatus::Warn => "⚠".yellow(),
+                doctor::CheckStatus::Fail => "✗".red(),
+            };
+            println!("  {} {}: {}", symbol, check.name, check.detail.dimmed());
+        }
+        println!();
+    }
+
+    let failures = report.count(doctor::CheckStatus::Fail);
+    if failures > 0 {
+        anyhow::bail!("{} check(s) failed", failures);
+    }
+    if !json {
+        ui::success(&format!(
+            "All checks passed ({} warning(s))",
+            report.count(doctor::CheckStatus::Warn)
+        ));
+    }
     Ok(())
 }
 
-/// Connect directly to a server by name or index.
-fn direct_connect(config: &Config, server_ref: &str, conn_type_str: &str) -> Result<()> {
-    // Find server by name or index
-    let server_index = if let Ok(index) = server_ref.parse::<usize>() {
+/// Find a server by name (case-insensitive) or 1-based index.
+fn find_server(config: &Config, server_ref: &str) -> Result<usize> {
+    if let Ok(index) = server_ref.parse::<usize>() {
         if index < 1 || index > config.servers.len() {
             anyhow::bail!(
                 "Server index {} out of range (1-{})",
@@ -212,15 +605,99 @@ fn direct_connect(config: &Config, server_ref: &str, conn_type_str: &str) -> Res
                 config.servers.len()
             );
         }
-        index - 1
-    } else {
-        config
-            .servers
-            .iter()
-            .position(|s| s.name.to_lowercase() == server_ref.to_lowercase())
-            .with_context(|| format!("Server '{}' not found", server_ref))?
+        return Ok(index - 1);
+    }
+
+    config
+        .servers
+        .iter()
+        .position(|s| s.name.to_lowercase() == server_ref.to_lowercase())
+        .with_context(|| format!("Server '{}' not found", server_ref))
+}
+
+/// Add, edit, remove or rename a server in the configuration file, keeping
+/// its comments and formatting.
+fn edit_servers(path: &Path, action: ServerAction) -> Result<()> {
+    if !path.exists() {
+        anyhow::bail!(
+            "Config file not found at '{}'. Run 'sap_it init' to create one",
+            path.display()
+        );
+    }
+    let mut file = ConfigFile::open(path)?;
+    let config = file.config()?;
+
+    let message = match action {
+        ServerAction::Add { name, fields } => {
+            if fields.rdp.is_none() {
+                anyhow::bail!("--rdp is required when adding a server");
+            }
+            let mut server = config::Server {
+                name,
+                ..Default::default()
+            };
+            fields.apply(&mut server)?;
+            file.add_server(&server)?;
+            format!("Added server '{}'", server.name)
+        }
+        ServerAction::Edit { server, fields } => {
+            let index = find_server(&config, &server)?;
+            let mut server = config.servers[index].clone();
+            fields.apply(&mut server)?;
+            file.set_server(index, &server)?;
+            format!("Updated server '{}'", server.name)
+        }
+        ServerAction::Rm { server } => {
+            let index = find_server(&config, &server)?;
+            file.remove_server(index)?;
+            format!("Removed server '{}'", config.servers[index].name)
+        }
+        ServerAction::Mv { server, new_name } => {
+            let index = find_server(&config, &server)?;
+            let mut server = config.servers[index].clone();
+            let old_name = std::mem::replace(&mut server.name, new_name);
+            file.set_server(index, &server)?;
+            format!("Renamed server '{}' to '{}'", old_name, server.name)
+        }
     };
 
+    let existing = file.save()?;
+    ui::success(&message);
+    for issue in existing {
+        ui::warning(&format!("{} (already in the file)", issue));
+    }
+    Ok(())
+}
+
+/// Connect directly to a server by name or index.
+///
+/// With `json`, progress is printed as one JSON event per line instead of
+/// the human-readable output, ending with `session_ended` or, whatever went
+/// wrong, a single `failed` event.
+fn direct_connect(
+    config_path: Option<&PathBuf>,
+    server_ref: &str,
+    conn_type_str: &str,
+    json: bool,
+) -> Result<()> {
+    let result = connect_server(config_path, server_ref, conn_type_str, json);
+    if let (true, Err(e)) = (json, &result) {
+        print_json_line(&FailedEvent::new(e));
+    }
+    result
+}
+
+/// Load the configuration and connect to a server, printing events as JSON
+/// lines with `json`.
+fn connect_server(
+    config_path: Option<&PathBuf>,
+    server_ref: &str,
+    conn_type_str: &str,
+    json: bool,
+) -> Result<()> {
+    let config = load_config(config_path, !json)?;
+    let server_index = find_server(&config, server_ref)?;
+
     // Parse connection type
     let conn_type = match conn_type_str.to_lowercase().as_str() {
         "rdp" => ConnectionType::Rdp,
@@ -237,27 +714,79 @@ fn direct_connect(config: &Config, server_ref: &str, conn_type_str: &str) -> Res
     // Check if SSH is requested but not available
     if (conn_type == ConnectionType::Ssh || conn_type == ConnectionType::Both) && !server.has_ssh()
     {
-        anyhow::bail!("SSH not available for server '{}'", server.name);
+        return Err(ConnectError::NoSsh {
+            server: server.name.clone(),
+        }
+        .into());
     }
 
     // Set up graceful shutdown
     let shutdown_flag = setup_shutdown_handler();
 
-    ui::display_header();
-    ui::display_connection_info(server, conn_type);
-
     // Create connection manager and connect
-    let manager = ConnectionManager::new(server.clone(), config.settings.clone(), shutdown_flag);
+    let mut manager =
+        ConnectionManager::new(server.clone(), config.settings.clone(), shutdown_flag);
+
+    if !json {
+        ui::display_header();
+        ui::display_connection_info(server, conn_type);
+        manager.connect(conn_type)?;
+        ui::success("Session ended");
+        return Ok(());
+    }
 
+    manager = manager.with_events(|event| print_json_line(&event));
     manager.connect(conn_type)?;
-
-    ui::success("Session ended");
     Ok(())
 }
 
+/// Last event of a failed `connect --json` run.
+#[derive(Serialize)]
+struct FailedEvent {
+    event: &'static str,
+    error: String,
+    hint: String,
+    exit_code: i32,
+}
+
+impl FailedEvent {
+    /// Describe `error` with the exit code the process ends with.
+    fn new(error: &anyhow::Error) -> Self {
+        match error.downcast_ref::<ConnectError>() {
+            Some(e) => Self {
+                event: "failed",
+                error: e.to_string(),
+                hint: e.hint(),
+                exit_code: e.exit_code(),
+            },
+            None => Self {
+                event: "failed",
+                error: format!("{:#}", error),
+                hint: "Check the config with `sap_it validate` and the servers with `sap_it list`"
+                    .to_string(),
+                exit_code: 1,
+            },
+        }
+    }
+}
+
+/// Print a value as one line of JSON.
+fn print_json_line(value: &impl Serialize) {
+    match serde_json::to_string(value) {
+        Ok(line) => println!("{}", line),
+        Err(e) => debug!("Failed to serialize event: {}", e),
+    }
+}
+
 /// Run the TUI mode.
 fn tui_mode(config_path: Option<&PathBuf>) -> Result<()> {
     let config = load_config(config_path, false)?;
+    let config_path = config_path.cloned().unwrap_or_else(Config::default_path);
+
+    // Raw mode turns Ctrl+C into a key event; SIGINT only arrives while the
+    // terminal is handed to ssh, which handles it itself
+    ctrlc::set_handler(|| debug!("Ignoring SIGINT while ssh owns the terminal"))
+        .context("Error setting Ctrl+C handler")?;
 
     // Setup terminal
     enable_raw_mode()?;
@@ -267,13 +796,13 @@ fn tui_mode(config_path: Option<&PathBuf>) -> Result<()> {
     let mut terminal = Terminal::new(backend)?;
 
     // Create app state
-    let mut app = tui::App::new(config);
+    let mut app = tui::App::new(config, config_path);
 
     // Event handler
-    let event_handler = tui::EventHandler::new(250); // 250ms tick rate
+    let mut event_handler = tui::EventHandler::new(TICK_RATE_MS);
 
     // Main loop
-    let result = run_tui_loop(&mut terminal, &mut app, &event_handler);
+    let result = run_tui_loop(&mut terminal, &mut app, &mut event_handler);
 
     // Restore terminal
     disable_raw_mode()?;
@@ -287,9 +816,21 @@ fn tui_mode(config_path: Option<&PathBuf>) -> Result<()> {
 fn run_tui_loop(
     terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
     app: &mut tui::App,
-    event_handler: &tui::EventHandler,
+    event_handler: &mut tui::EventHandler,
 ) -> Result<()> {
     while !app.should_quit {
+        if let Some((session, server)) = app.take_ssh_request() {
+            let result = run_ssh_session(terminal, &server);
+            app.ssh_finished(session, result);
+        }
+
+        // Redraw often enough to keep an embedded terminal responsive
+        event_handler.set_tick_rate(if app.pty().is_some() {
+            PTY_TICK_RATE_MS
+        } else {
+            TICK_RATE_MS
+        });
+
         // Render UI
         terminal.draw(|frame| {
             tui::ui::render(app, frame);
@@ -298,8 +839,9 @@ fn run_tui_loop(
         // Handle events
         match event_handler.next()? {
             tui::Event::Tick => {
-                // Update connection status on tick
-                app.update_connection();
+                // Update sessions and reachability status on tick
+                app.update_sessions();
+                app.poll_status();
             }
             tui::Event::Key(key) => {
                 tui::event::handle_key_event(app, key);
@@ -316,6 +858,31 @@ fn run_tui_loop(
     Ok(())
 }
 
+/// Suspend the TUI, run an interactive SSH session, then restore the TUI.
+fn run_ssh_session(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    server: &config::Server,
+) -> Result<()> {
+    let ssh = server
+        .ssh_config()
+        .context("SSH not available for this server")?;
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+
+    println!("Connecting to {} via SSH ({})...", server.name, ssh);
+    let result = platform::start_ssh(ssh, server.jump_host());
+
+    enable_raw_mode()?;
+    execute!(terminal.backend_mut(), EnterAlternateScreen)?;
+    terminal.clear()?;
+
+    let (status, errors) = result?;
+    check_ssh_exit(ssh, status.code(), &errors)?;
+    Ok(())
+}
+
 /// Run in simple text interactive mode.
 fn simple_interactive_mode(config_path: Option<&PathBuf>) -> Result<()> {
     let config = load_config(config_path, true)?;
@@ -6,62 +6,114 @@ mod windows;
 #[cfg(not(windows))]
 mod unix;
 
+pub mod rdp;
+pub mod ssh;
+pub mod vpn;
+
+use crate::config::{RdpClient, RdpConfig, SshConfig};
 use anyhow::Result;
-use std::process::Child;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::process::{Child, ExitStatus};
+use std::time::Duration;
+use tracing::debug;
 
-/// Connect to a VPN by name.
+/// Ping a host to check connectivity.
 #[cfg(windows)]
-pub fn connect_vpn(vpn_name: &str) -> Result<()> {
-    windows::connect_vpn(vpn_name)
+pub fn ping_host(host: &str, timeout_ms: u32) -> bool {
+    windows::ping_host(host, timeout_ms)
 }
 
 #[cfg(not(windows))]
-pub fn connect_vpn(vpn_name: &str) -> Result<()> {
-    unix::connect_vpn(vpn_name)
+pub fn ping_host(host: &str, timeout_ms: u32) -> bool {
+    unix::ping_host(host, timeout_ms)
+}
+
+/// Check whether a TCP port accepts connections.
+pub fn tcp_probe(host: &str, port: u16, timeout_ms: u32) -> bool {
+    let timeout = Duration::from_millis(timeout_ms.max(1) as u64);
+
+    let addrs = match (host, port).to_socket_addrs() {
+        Ok(addrs) => addrs,
+        Err(e) => {
+            debug!("Failed to resolve {}: {}", host, e);
+            return false;
+        }
+    };
+
+    for addr in addrs {
+        debug!("TCP probe: {}", addr);
+        if TcpStream::connect_timeout(&addr, timeout).is_ok() {
+            return true;
+        }
+    }
+
+    false
 }
 
-/// Disconnect from a VPN by name.
+/// Find a program on the PATH, as the shell would.
+pub fn find_executable(name: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    let file = format!("{}{}", name, std::env::consts::EXE_SUFFIX);
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(&file))
+        .find(|candidate| candidate.is_file())
+}
+
+/// RDP client program(s) used by `start_rdp`, for error messages.
 #[cfg(windows)]
-pub fn disconnect_vpn(vpn_name: &str) -> Result<()> {
-    windows::disconnect_vpn(vpn_name)
+pub const RDP_CLIENT: &str = "mstsc";
+
+#[cfg(not(windows))]
+pub const RDP_CLIENT: &str = "xfreerdp or rdesktop";
+
+/// Start an RDP session with the given connection options, trying the
+/// `preferred` client first.
+#[cfg(windows)]
+pub fn start_rdp(rdp: &RdpConfig, preferred: RdpClient) -> Result<Child> {
+    windows::start_rdp(rdp, preferred)
 }
 
 #[cfg(not(windows))]
-pub fn disconnect_vpn(vpn_name: &str) -> Result<()> {
-    unix::disconnect_vpn(vpn_name)
+pub fn start_rdp(rdp: &RdpConfig, preferred: RdpClient) -> Result<Child> {
+    unix::start_rdp(rdp, preferred)
 }
 
-/// Ping a host to check connectivity.
+/// Start an SSH session to the specified target, optionally via a jump host.
+///
+/// Blocks until the session ends and returns the ssh client's exit status,
+/// with the end of its error output.
 #[cfg(windows)]
-pub fn ping_host(host: &str, timeout_ms: u32) -> bool {
-    windows::ping_host(host, timeout_ms)
+pub fn start_ssh(target: &SshConfig, jump: Option<&SshConfig>) -> Result<(ExitStatus, String)> {
+    windows::start_ssh(&ssh::ssh_args(target, jump))
 }
 
 #[cfg(not(windows))]
-pub fn ping_host(host: &str, timeout_ms: u32) -> bool {
-    unix::ping_host(host, timeout_ms)
+pub fn start_ssh(target: &SshConfig, jump: Option<&SshConfig>) -> Result<(ExitStatus, String)> {
+    unix::start_ssh(&ssh::ssh_args(target, jump))
 }
 
-/// Start an RDP session to the specified address.
+/// Terminate a process by PID.
 #[cfg(windows)]
-pub fn start_rdp(address: &str) -> Result<Child> {
-    windows::start_rdp(address)
+pub fn kill_process(pid: u32) -> Result<()> {
+    windows::kill_process(pid)
 }
 
 #[cfg(not(windows))]
-pub fn start_rdp(address: &str) -> Result<Child> {
-    unix::start_rdp(address)
+pub fn kill_process(pid: u32) -> Result<()> {
+    unix::kill_process(pid)
 }
 
-/// Start an SSH session to the specified target.
+/// Name of the program running as `pid` (without `.exe`), or None if there
+/// is no such process.
 #[cfg(windows)]
-pub fn start_ssh(target: &str) -> Result<()> {
-    windows::start_ssh(target)
+pub fn process_name(pid: u32) -> Option<String> {
+    windows::process_name(pid)
 }
 
 #[cfg(not(windows))]
-pub fn start_ssh(target: &str) -> Result<()> {
-    unix::start_ssh(target)
+pub fn process_name(pid: u32) -> Option<String> {
+    unix::process_name(pid)
 }
 
 /// Clear the terminal screen.
@@ -74,3 +126,26 @@ pub fn clear_screen() {
 pub fn clear_screen() {
     unix::clear_screen()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_tcp_probe() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        assert!(tcp_probe("127.0.0.1", port, 500));
+
+        drop(listener);
+        assert!(!tcp_probe("127.0.0.1", port, 500));
+    }
+
+    #[test]
+    fn test_process_name() {
+        let name = process_name(std::process::id()).unwrap();
+        assert!(name.starts_with("sap_it"), "unexpected name {}", name);
+        assert_eq!(process_name(u32::MAX), None);
+    }
+}
@@ -0,0 +1,292 @@
+//! RDP client command-line and .rdp file construction.
+
+use crate::config::{CertPolicy, RdpConfig};
+use std::path::Path;
+use tracing::warn;
+
+/// FreeRDP major version, which changes some option spellings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(windows, allow(dead_code))]
+pub enum FreeRdp {
+    /// `xfreerdp` (FreeRDP 2.x).
+    V2,
+    /// `xfreerdp3` (FreeRDP 3.x).
+    V3,
+}
+
+/// Build xfreerdp/xfreerdp3 arguments.
+#[cfg_attr(windows, allow(dead_code))]
+pub fn xfreerdp_args(rdp: &RdpConfig, version: FreeRdp) -> Vec<String> {
+    let mut args = vec![format!("/v:{}", rdp)];
+
+    if let Some(username) = &rdp.username {
+        args.push(format!("/u:{}", username));
+    }
+    if let Some(domain) = &rdp.domain {
+        args.push(format!("/d:{}", domain));
+    }
+
+    if rdp.fullscreen {
+        args.push("/f".to_string());
+    } else if let Some((width, height)) = rdp.resolution_size() {
+        args.push(format!("/size:{}x{}", width, height));
+    } else {
+        if rdp.resolution.is_some() {
+            warn!("Ignoring invalid RDP resolution, expected WIDTHxHEIGHT");
+        }
+        args.push("/dynamic-resolution".to_string());
+    }
+
+    if rdp.multimon {
+        args.push("/multimon".to_string());
+    }
+
+    args.push(if rdp.clipboard {
+        "+clipboard".to_string()
+    } else {
+        "-clipboard".to_string()
+    });
+
+    for (i, drive) in rdp.drives.iter().enumerate() {
+        args.push(format!("/drive:{},{}", drive_name(drive, i), drive));
+    }
+
+    if let Some(gateway) = &rdp.gateway {
+        args.push(match version {
+            FreeRdp::V2 => format!("/g:{}", gateway),
+            FreeRdp::V3 => format!("/gateway:g:{}", gateway),
+        });
+    }
+
+    match rdp.cert {
+        CertPolicy::Ignore => args.push("/cert:ignore".to_string()),
+        CertPolicy::Tofu => args.push("/cert:tofu".to_string()),
+        CertPolicy::Strict => {}
+    }
+
+    args
+}
+
+/// Build rdesktop arguments.
+#[cfg_attr(windows, allow(dead_code))]
+pub fn rdesktop_args(rdp: &RdpConfig) -> Vec<String> {
+    let mut args = Vec::new();
+
+    if let Some(username) = &rdp.username {
+        args.push("-u".to_string());
+        args.push(username.clone());
+    }
+    if let Some(domain) = &rdp.domain {
+        args.push("-d".to_string());
+        args.push(domain.clone());
+    }
+
+    if rdp.fullscreen {
+        args.push("-f".to_string());
+    } else if let Some((width, height)) = rdp.resolution_size() {
+        args.push("-g".to_string());
+        args.push(format!("{}x{}", width, height));
+    }
+
+    if rdp.clipboard {
+        args.push("-r".to_string());
+        args.push("clipboard:PRIMARYCLIPBOARD".to_string());
+    }
+
+    for (i, drive) in rdp.drives.iter().enumerate() {
+        args.push("-r".to_string());
+        args.push(format!("disk:{}={}", drive_name(drive, i), drive));
+    }
+
+    if rdp.multimon {
+        warn!("rdesktop does not support multi-monitor sessions");
+    }
+    if rdp.gateway.is_some() {
+        warn!("rdesktop does not support RD Gateway, connecting directly");
+    }
+
+    args.push(rdp.to_string());
+    args
+}
+
+/// Whether the options need a generated .rdp file for mstsc.
+#[cfg_attr(not(windows), allow(dead_code))]
+pub fn needs_rdp_file(rdp: &RdpConfig) -> bool {
+    rdp.username.is_some()
+        || rdp.domain.is_some()
+        || !rdp.clipboard
+        || !rdp.drives.is_empty()
+        || rdp.gateway.is_some()
+        || rdp.cert != CertPolicy::Ignore
+}
+
+/// Build mstsc arguments, optionally pointing at a generated .rdp file.
+#[cfg_attr(not(windows), allow(dead_code))]
+pub fn mstsc_args(rdp: &RdpConfig, rdp_file: Option<&Path>) -> Vec<String> {
+    let mut args = Vec::new();
+
+    if let Some(file) = rdp_file {
+        args.push(file.display().to_string());
+    }
+
+    args.push(format!("/v:{}", rdp));
+
+    if rdp.fullscreen {
+        args.push("/f".to_string());
+    } else if let Some((width, height)) = rdp.resolution_size() {
+        args.push(format!("/w:{}", width));
+        args.push(format!("/h:{}", height));
+    }
+
+    if rdp.multimon {
+        args.push("/multimon".to_string());
+    }
+
+    args
+}
+
+/// Generate the contents of an .rdp file for mstsc.
+#[cfg_attr(not(windows), allow(dead_code))]
+pub fn rdp_file_contents(rdp: &RdpConfig) -> String {
+    let mut lines = vec![format!("full address:s:{}", rdp)];
+
+    if let Some(username) = &rdp.username {
+        let username = match &rdp.domain {
+            Some(domain) => format!("{}\\{}", domain, username),
+            None => username.clone(),
+        };
+        lines.push(format!("username:s:{}", username));
+    } else if let Some(domain) = &rdp.domain {
+        lines.push(format!("domain:s:{}", domain));
+    }
+
+    if rdp.fullscreen {
+        lines.push("screen mode id:i:2".to_string());
+    } else if let Some((width, height)) = rdp.resolution_size() {
+        lines.push("screen mode id:i:1".to_string());
+        lines.push(format!("desktopwidth:i:{}", width));
+        lines.push(format!("desktopheight:i:{}", height));
+    } else {
+        lines.push("dynamic resolution:i:1".to_string());
+    }
+
+    lines.push(format!("use multimon:i:{}", rdp.multimon as u8));
+    lines.push(format!("redirectclipboard:i:{}", rdp.clipboard as u8));
+
+    if !rdp.drives.is_empty() {
+        lines.push(format!("drivestoredirect:s:{}", rdp.drives.join(";")));
+    }
+
+    if let Some(gateway) = &rdp.gateway {
+        lines.push(format!("gatewayhostname:s:{}", gateway));
+        lines.push("gatewayusagemethod:i:1".to_string());
+        lines.push("gatewayprofileusagemethod:i:1".to_string());
+    }
+
+    let authentication_level = match rdp.cert {
+        CertPolicy::Ignore => 0,
+        CertPolicy::Strict => 1,
+        CertPolicy::Tofu => 2,
+    };
+    lines.push(format!("authentication level:i:{}", authentication_level));
+
+    let mut contents = lines.join("\r\n");
+    contents.push_str("\r\n");
+    contents
+}
+
+/// Share name for a redirected drive.
+fn drive_name(path: &str, index: usize) -> String {
+    Path::new(path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .filter(|name| !name.is_empty())
+        .map(|name| name.to_string())
+        .unwrap_or_else(|| format!("drive{}", index + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_args_match_previous_behavior() {
+        let rdp: RdpConfig = "192.168.0.99".parse().unwrap();
+        assert_eq!(
+            xfreerdp_args(&rdp, FreeRdp::V2),
+            vec![
+                "/v:192.168.0.99",
+                "/dynamic-resolution",
+                "+clipboard",
+                "/cert:ignore"
+            ]
+        );
+        assert!(!needs_rdp_file(&rdp));
+        assert_eq!(mstsc_args(&rdp, None), vec!["/v:192.168.0.99"]);
+    }
+
+    #[test]
+    fn test_xfreerdp_options() {
+        let rdp = RdpConfig {
+            host: "fe80::1".to_string(),
+            port: Some(3390),
+            username: Some("admin".to_string()),
+            domain: Some("CORP".to_string()),
+            resolution: Some("1920x1080".to_string()),
+            multimon: true,
+            clipboard: false,
+            drives: vec!["/home/me/share".to_string()],
+            gateway: Some("gw.example.com".to_string()),
+            cert: CertPolicy::Tofu,
+            ..Default::default()
+        };
+        assert_eq!(
+            xfreerdp_args(&rdp, FreeRdp::V3),
+            vec![
+                "/v:[fe80::1]:3390",
+                "/u:admin",
+                "/d:CORP",
+                "/size:1920x1080",
+                "/multimon",
+                "-clipboard",
+                "/drive:share,/home/me/share",
+                "/gateway:g:gw.example.com",
+                "/cert:tofu",
+            ]
+        );
+        assert_eq!(
+            rdesktop_args(&rdp),
+            vec![
+                "-u",
+                "admin",
+                "-d",
+                "CORP",
+                "-g",
+                "1920x1080",
+                "-r",
+                "disk:share=/home/me/share",
+                "[fe80::1]:3390",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rdp_file_contents() {
+        let rdp = RdpConfig {
+            host: "server.example".to_string(),
+            username: Some("admin".to_string()),
+            domain: Some("CORP".to_string()),
+            fullscreen: true,
+            gateway: Some("gw.example.com".to_string()),
+            ..Default::default()
+        };
+        assert!(needs_rdp_file(&rdp));
+
+        let contents = rdp_file_contents(&rdp);
+        assert!(contents.contains("full address:s:server.example\r\n"));
+        assert!(contents.contains("username:s:CORP\\admin\r\n"));
+        assert!(contents.contains("screen mode id:i:2\r\n"));
+        assert!(contents.contains("gatewayhostname:s:gw.example.com\r\n"));
+        assert!(contents.contains("authentication level:i:0\r\n"));
+    }
+}
@@ -0,0 +1,367 @@
+//! SSH client command-line construction and port-forward tunnels.
+
+use crate::config::SshConfig;
+use anyhow::{Context, Result};
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::process::{Child, ChildStderr, Command, ExitStatus, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// How long to wait for a tunnel's local port to start accepting connections.
+const TUNNEL_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// How long to wait for the rest of a failed tunnel's error output.
+const STDERR_GRACE: Duration = Duration::from_millis(500);
+
+/// Bytes kept from the end of an interactive session's error output.
+const STDERR_TAIL: usize = 4096;
+
+/// Build the ssh client arguments for a target, optionally via a jump host.
+///
+/// A plain jump host is passed as `-J`. One with an `identity_file` or
+/// `options`, which `-J` can't carry, becomes a `ProxyCommand` running
+/// `ssh -W` with the jump host's own arguments.
+pub fn ssh_args(target: &SshConfig, jump: Option<&SshConfig>) -> Vec<String> {
+    let mut args = Vec::new();
+
+    if let Some(jump) = jump {
+        if jump.identity_file.is_some() || !jump.options.is_empty() {
+            args.push("-o".to_string());
+            args.push(proxy_command(jump));
+        } else {
+            args.push("-J".to_string());
+            args.push(jump_spec(jump));
+        }
+    }
+
+    if let Some(port) = target.port {
+        args.push("-p".to_string());
+        args.push(port.to_string());
+    }
+
+    if let Some(user) = &target.user {
+        args.push("-l".to_string());
+        args.push(user.clone());
+    }
+
+    if let Some(identity) = &target.identity_file {
+        args.push("-i".to_string());
+        args.push(identity.display().to_string());
+    }
+
+    for option in &target.options {
+        args.push("-o".to_string());
+        args.push(option.clone());
+    }
+
+    args.push(target.host.clone());
+    args
+}
+
+/// A jump host as `-J` takes it, with IPv6 addresses always in brackets.
+fn jump_spec(jump: &SshConfig) -> String {
+    let mut spec = String::new();
+    if let Some(user) = &jump.user {
+        spec.push_str(user);
+        spec.push('@');
+    }
+    if jump.host.contains(':') {
+        spec.push_str(&format!("[{}]", jump.host));
+    } else {
+        spec.push_str(&jump.host);
+    }
+    if let Some(port) = jump.port {
+        spec.push_str(&format!(":{}", port));
+    }
+    spec
+}
+
+/// A `ProxyCommand` option reaching the target through `jump` with `ssh -W`.
+fn proxy_command(jump: &SshConfig) -> String {
+    let command: Vec<String> = std::iter::once("ssh".to_string())
+        .chain(ssh_args(jump, None))
+        .map(|arg| proxy_quote(&arg.replace('%', "%%")))
+        .collect();
+    format!("ProxyCommand={} -W [%h]:%p", command.join(" "))
+}
+
+/// Quote an argument for the shell ssh runs the `ProxyCommand` with.
+fn proxy_quote(arg: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "-_.,:/=@%+[]".contains(c);
+    if !arg.is_empty() && arg.chars().all(plain) {
+        arg.to_string()
+    } else if cfg!(windows) {
+        format!("\"{}\"", arg.replace('"', "\\\""))
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// A local SSH port-forward, closed when dropped.
+pub struct Tunnel {
+    child: Child,
+    local_port: u16,
+    /// Everything ssh wrote to stderr, once it closes it.
+    stderr: Option<Receiver<String>>,
+}
+
+impl Tunnel {
+    /// Forward a free local port to `remote_host:remote_port` through `jump`.
+    ///
+    /// Blocks until the local port accepts connections or the ssh client exits.
+    /// The tunnel runs in batch mode, so it can't ask for passwords; when it
+    /// fails, ssh's error output is part of the error.
+    pub fn open(jump: &SshConfig, remote_host: &str, remote_port: u16) -> Result<Self> {
+        let local_port = free_local_port()?;
+        let remote_host = if remote_host.contains(':') {
+            format!("[{}]", remote_host)
+        } else {
+            remote_host.to_string()
+        };
+
+        let mut args = vec![
+            "-N".to_string(),
+            "-o".to_string(),
+            "ExitOnForwardFailure=yes".to_string(),
+            "-o".to_string(),
+            "BatchMode=yes".to_string(),
+            "-L".to_string(),
+            format!("127.0.0.1:{}:{}:{}", local_port, remote_host, remote_port),
+        ];
+        args.extend(ssh_args(jump, None));
+
+        debug!("Executing: ssh {}", args.join(" "));
+
+        let mut child = Command::new("ssh")
+            .args(&args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to start ssh tunnel")?;
+        let stderr = child.stderr.take().map(collect_output);
+
+        let mut tunnel = Self {
+            child,
+            local_port,
+            stderr,
+        };
+        tunnel.wait_until_ready()?;
+
+        Ok(tunnel)
+    }
+
+    /// Local port forwarded to the remote endpoint.
+    pub fn local_port(&self) -> u16 {
+        self.local_port
+    }
+
+    fn wait_until_ready(&mut self) -> Result<()> {
+        let start = Instant::now();
+
+        while start.elapsed() < TUNNEL_TIMEOUT {
+            if let Some(status) = self.child.try_wait()? {
+                return Err(self.failure(format!("ssh tunnel exited with {}", status)));
+            }
+            if super::tcp_probe("127.0.0.1", self.local_port, 200) {
+                debug!("Tunnel ready on 127.0.0.1:{}", self.local_port);
+                return Ok(());
+            }
+            std::thread::sleep(Duration::from_millis(200));
+        }
+
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        Err(self.failure(format!(
+            "ssh tunnel did not come up within {}s",
+            TUNNEL_TIMEOUT.as_secs()
+        )))
+    }
+
+    /// An error for the stopped tunnel, with what ssh said about it.
+    fn failure(&mut self, message: String) -> anyhow::Error {
+        let output = self
+            .stderr
+            .take()
+            .and_then(|stderr| stderr.recv_timeout(STDERR_GRACE).ok())
+            .unwrap_or_default();
+        let lines: Vec<&str> = output
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .collect();
+
+        if lines.is_empty() {
+            anyhow::anyhow!(message)
+        } else {
+            anyhow::anyhow!("{}: {}", message, lines.join("; "))
+        }
+    }
+}
+
+impl Drop for Tunnel {
+    fn drop(&mut self) {
+        debug!("Closing tunnel on 127.0.0.1:{}", self.local_port);
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Run an interactive ssh `command` and wait for it to end.
+///
+/// ssh's stderr is passed through to ours, so its messages still reach the
+/// terminal; the end of it is returned with the exit status to tell why
+/// ssh failed. Prompts go to the terminal directly and aren't affected.
+pub fn run_interactive(command: &mut Command) -> Result<(ExitStatus, String)> {
+    let mut child = command
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to execute ssh")?;
+    let stderr = child.stderr.take().map(pass_through);
+
+    let status = child.wait().context("Failed to wait for ssh")?;
+    let errors = stderr
+        .and_then(|stderr| stderr.recv_timeout(STDERR_GRACE).ok())
+        .unwrap_or_default();
+    Ok((status, errors))
+}
+
+/// Copy `stderr` to ours on a separate thread, keeping the last
+/// `STDERR_TAIL` bytes; they are sent once it closes.
+fn pass_through(mut stderr: ChildStderr) -> Receiver<String> {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut tail = Vec::new();
+        let mut buffer = [0; 1024];
+        let mut out = std::io::stderr();
+        while let Ok(n @ 1..) = stderr.read(&mut buffer) {
+            let _ = out.write_all(&buffer[..n]);
+            let _ = out.flush();
+            tail.extend_from_slice(&buffer[..n]);
+            if tail.len() > STDERR_TAIL {
+                tail.drain(..tail.len() - STDERR_TAIL);
+            }
+        }
+        let _ = sender.send(String::from_utf8_lossy(&tail).into_owned());
+    });
+    receiver
+}
+
+/// Read `stderr` to the end on a separate thread, so a full pipe never
+/// blocks ssh; the output is sent once it closes.
+fn collect_output(mut stderr: ChildStderr) -> Receiver<String> {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut output = Vec::new();
+        let _ = stderr.read_to_end(&mut output);
+        let _ = sender.send(String::from_utf8_lossy(&output).into_owned());
+    });
+    receiver
+}
+
+/// Ask the OS for a currently unused local TCP port.
+fn free_local_port() -> Result<u16> {
+    let listener = TcpListener::bind("127.0.0.1:0").context("Failed to find a free local port")?;
+    Ok(listener.local_addr()?.port())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_ssh_args_short_form() {
+        let target: SshConfig = "root@192.168.0.98".parse().unwrap();
+        assert_eq!(ssh_args(&target, None), vec!["-l", "root", "192.168.0.98"]);
+    }
+
+    #[test]
+    fn test_ssh_args_full() {
+        let target = SshConfig {
+            host: "fe80::1".to_string(),
+            port: Some(2222),
+            user: Some("deploy".to_string()),
+            identity_file: Some(PathBuf::from("/home/me/.ssh/id_ed25519")),
+            options: vec!["ServerAliveInterval=30".to_string()],
+        };
+        assert_eq!(
+            ssh_args(&target, None),
+            vec![
+                "-p",
+                "2222",
+                "-l",
+                "deploy",
+                "-i",
+                "/home/me/.ssh/id_ed25519",
+                "-o",
+                "ServerAliveInterval=30",
+                "fe80::1",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ssh_args_with_jump() {
+        let target: SshConfig = "root@10.1.0.10".parse().unwrap();
+        let jump: SshConfig = "admin@bastion.example.com:2222".parse().unwrap();
+        assert_eq!(
+            ssh_args(&target, Some(&jump)),
+            vec![
+                "-J",
+                "admin@bastion.example.com:2222",
+                "-l",
+                "root",
+                "10.1.0.10"
+            ]
+        );
+
+        // IPv6 jump hosts are bracketed even without a port
+        let jump: SshConfig = "admin@[fe80::1]".parse().unwrap();
+        assert_eq!(ssh_args(&target, Some(&jump))[1], "admin@[fe80::1]");
+        let jump: SshConfig = "[fe80::1]:2222".parse().unwrap();
+        assert_eq!(ssh_args(&target, Some(&jump))[1], "[fe80::1]:2222");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_ssh_args_with_jump_identity() {
+        let target: SshConfig = "root@10.1.0.10".parse().unwrap();
+        let jump = SshConfig {
+            host: "bastion.example.com".to_string(),
+            port: None,
+            user: Some("ops".to_string()),
+            identity_file: Some(PathBuf::from("/home/me/my keys/id_ed25519")),
+            options: vec!["ServerAliveInterval=30".to_string()],
+        };
+        assert_eq!(
+            ssh_args(&target, Some(&jump)),
+            vec![
+                "-o",
+                "ProxyCommand=ssh -l ops -i '/home/me/my keys/id_ed25519' \
+                 -o ServerAliveInterval=30 bastion.example.com -W [%h]:%p",
+                "-l",
+                "root",
+                "10.1.0.10"
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_tunnel_error_includes_ssh_output() {
+        // An unresolvable host makes ssh fail right away, before any prompt
+        let jump: SshConfig = "nobody@sap-it-test.invalid".parse().unwrap();
+        let error = match Tunnel::open(&jump, "10.0.0.1", 3389) {
+            Ok(_) => panic!("tunnel to an invalid host opened"),
+            Err(e) => e.to_string(),
+        };
+        if error.contains("Failed to start") {
+            return; // ssh not installed
+        }
+        assert!(error.contains("ssh tunnel exited with"), "{}", error);
+        assert!(error.contains("sap-it-test.invalid"), "{}", error);
+    }
+}
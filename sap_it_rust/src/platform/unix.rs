@@ -1,66 +1,11 @@
 //! Unix/Linux-specific implementations.
 
+use super::rdp::{self, FreeRdp};
+use crate::config::{RdpClient, RdpConfig};
 use anyhow::{Context, Result};
-use std::process::{Child, Command, Stdio};
-use tracing::{debug, warn};
-
-/// Connect to a VPN using nmcli (NetworkManager) or openconnect.
-pub fn connect_vpn(vpn_name: &str) -> Result<()> {
-    // Try NetworkManager first
-    debug!("Attempting VPN connection via nmcli: {}", vpn_name);
-
-    let result = Command::new("nmcli")
-        .args(["connection", "up", vpn_name])
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .status();
-
-    match result {
-        Ok(status) if status.success() => {
-            debug!("VPN connected via nmcli");
-            return Ok(());
-        }
-        Ok(_) => {
-            warn!("nmcli connection failed, VPN '{}' may not exist", vpn_name);
-        }
-        Err(e) => {
-            debug!("nmcli not available: {}", e);
-        }
-    }
-
-    // Fallback: try to use vpnc or other tools
-    warn!(
-        "NetworkManager VPN connection failed. Please ensure VPN '{}' is configured in NetworkManager.",
-        vpn_name
-    );
-
-    Ok(())
-}
-
-/// Disconnect from a VPN using nmcli.
-pub fn disconnect_vpn(vpn_name: &str) -> Result<()> {
-    debug!("Disconnecting VPN via nmcli: {}", vpn_name);
-
-    let result = Command::new("nmcli")
-        .args(["connection", "down", vpn_name])
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .status();
-
-    match result {
-        Ok(status) if status.success() => {
-            debug!("VPN disconnected via nmcli");
-        }
-        Ok(_) => {
-            warn!("nmcli disconnection returned non-zero status");
-        }
-        Err(e) => {
-            debug!("nmcli not available: {}", e);
-        }
-    }
-
-    Ok(())
-}
+use std::path::Path;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use tracing::debug;
 
 /// Ping a host using the ping command (Linux syntax).
 pub fn ping_host(host: &str, timeout_ms: u32) -> bool {
@@ -85,59 +30,85 @@ pub fn ping_host(host: &str, timeout_ms: u32) -> bool {
     }
 }
 
-/// Start an RDP session using xfreerdp or rdesktop.
-pub fn start_rdp(address: &str) -> Result<Child> {
-    // Try xfreerdp first (more modern, better protocol support)
-    debug!("Attempting RDP via xfreerdp: {}", address);
-
-    let xfreerdp_result = Command::new("xfreerdp")
-        .args([
-            &format!("/v:{}", address),
-            "/cert:ignore",
-            "/dynamic-resolution",
-        ])
-        .spawn();
-
-    if let Ok(child) = xfreerdp_result {
-        return Ok(child);
+/// Start an RDP session using xfreerdp, xfreerdp3 or rdesktop.
+///
+/// The `preferred` client is tried first, then the others in that order
+/// (xfreerdp is more modern and has better protocol support than rdesktop).
+pub fn start_rdp(rdp: &RdpConfig, preferred: RdpClient) -> Result<Child> {
+    const FALLBACKS: [RdpClient; 3] = [
+        RdpClient::Xfreerdp,
+        RdpClient::Xfreerdp3,
+        RdpClient::Rdesktop,
+    ];
+
+    let clients = std::iter::once(preferred)
+        .filter(|&client| client != RdpClient::Auto)
+        .chain(FALLBACKS.into_iter().filter(|&client| client != preferred));
+
+    let mut error = std::io::Error::from(std::io::ErrorKind::NotFound);
+    for client in clients {
+        let args = match client {
+            RdpClient::Xfreerdp => rdp::xfreerdp_args(rdp, FreeRdp::V2),
+            RdpClient::Xfreerdp3 => rdp::xfreerdp_args(rdp, FreeRdp::V3),
+            RdpClient::Rdesktop | RdpClient::Auto => rdp::rdesktop_args(rdp),
+        };
+        debug!("Attempting RDP via {}: {}", client.name(), rdp);
+
+        match Command::new(client.name()).args(args).spawn() {
+            Ok(child) => return Ok(child),
+            Err(e) => {
+                debug!("{} could not be started: {}", client.name(), e);
+                error = e;
+            }
+        }
     }
 
-    // Fallback to xfreerdp3 (newer version with different binary name)
-    debug!("xfreerdp not found, trying xfreerdp3...");
-
-    let xfreerdp3_result = Command::new("xfreerdp3")
-        .args([
-            &format!("/v:{}", address),
-            "/cert:ignore",
-            "/dynamic-resolution",
-        ])
-        .spawn();
-
-    if let Ok(child) = xfreerdp3_result {
-        return Ok(child);
-    }
+    Err(error).context("Failed to start RDP client. Please install xfreerdp or rdesktop.")
+}
 
-    // Fallback to rdesktop
-    debug!("xfreerdp3 not found, trying rdesktop...");
+/// Start an SSH session using the ssh command and wait for it to end.
+///
+/// Returns the exit status and the end of ssh's error output.
+pub fn start_ssh(args: &[String]) -> Result<(ExitStatus, String)> {
+    debug!("Executing: ssh {}", args.join(" "));
 
-    Command::new("rdesktop")
-        .arg(address)
-        .spawn()
-        .context("Failed to start RDP client. Please install xfreerdp or rdesktop.")
+    super::ssh::run_interactive(Command::new("ssh").args(args))
 }
 
-/// Start an SSH session using the ssh command.
-pub fn start_ssh(target: &str) -> Result<()> {
-    debug!("Executing: ssh {}", target);
+/// Terminate a process with SIGTERM.
+pub fn kill_process(pid: u32) -> Result<()> {
+    debug!("Executing: kill {}", pid);
 
-    Command::new("ssh")
-        .arg(target)
+    let status = Command::new("kill")
+        .arg(pid.to_string())
+        .stderr(Stdio::null())
         .status()
-        .context("Failed to execute ssh")?;
+        .context("Failed to execute kill")?;
+
+    if !status.success() {
+        anyhow::bail!("Failed to terminate process {}", pid);
+    }
 
     Ok(())
 }
 
+/// Name of the program running as `pid`, or None if there is no such process.
+pub fn process_name(pid: u32) -> Option<String> {
+    let output = Command::new("ps")
+        .args(["-p", &pid.to_string(), "-o", "comm="])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    // Some systems print the full path of the executable
+    let comm = String::from_utf8_lossy(&output.stdout);
+    let name = Path::new(comm.trim()).file_name()?;
+    Some(name.to_string_lossy().into_owned())
+}
+
 /// Clear the terminal screen.
 pub fn clear_screen() {
     print!("\x1B[2J\x1B[1;1H");
@@ -0,0 +1,958 @@
+//! Pluggable VPN backends.
+//!
+//! Every server selects a backend through its optional `[servers.vpn_backend]`
+//! table. Servers without one use the platform default: NetworkManager (nmcli)
+//! on Linux and rasphone on Windows.
+
+use crate::config::{Server, VpnBackendConfig};
+use anyhow::{Context, Result};
+use std::fmt;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::mpsc;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// A mechanism for bringing a VPN tunnel up and down.
+pub trait VpnBackend: Send {
+    /// Short backend name used in logs and status messages.
+    fn name(&self) -> &'static str;
+
+    /// Bring the tunnel up.
+    fn connect(&self) -> Result<()>;
+
+    /// Tear the tunnel down.
+    fn disconnect(&self) -> Result<()>;
+
+    /// Whether the tunnel is already up.
+    ///
+    /// Backends that cannot tell report false, so the tunnel is always
+    /// brought up (and torn down) by sap_it.
+    fn is_active(&self) -> Result<bool> {
+        Ok(false)
+    }
+}
+
+/// Why a VPN could not be brought up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VpnError {
+    /// No VPN connection with this name is configured.
+    VpnNotFound { vpn: String },
+    /// The VPN server rejected the credentials.
+    AuthFailed { vpn: String },
+    /// A password is needed but no secret agent provided one.
+    SecretsRequired { vpn: String },
+    /// The VPN client program is not installed.
+    BackendMissing { backend: &'static str },
+    /// The VPN client program failed without concerning a particular VPN,
+    /// e.g. NetworkManager is not running.
+    BackendFailed {
+        backend: &'static str,
+        message: String,
+    },
+    /// Any other failure reported by the client.
+    Failed { vpn: String, message: String },
+}
+
+impl VpnError {
+    /// What the user can do about the error.
+    pub fn hint(&self) -> String {
+        match self {
+            VpnError::VpnNotFound { vpn } => format!(
+                "Check that '{}' matches a connection listed by `nmcli connection show`",
+                vpn
+            ),
+            VpnError::AuthFailed { .. } => {
+                "Check the VPN username, password or certificate".to_string()
+            }
+            VpnError::SecretsRequired { vpn } => format!(
+                "Save the VPN password in NetworkManager, or run `nmcli --ask connection up \"{}\"` once",
+                vpn
+            ),
+            VpnError::BackendMissing { backend } => format!(
+                "Install {} or set another vpn_backend for this server",
+                backend
+            ),
+            VpnError::BackendFailed { .. } | VpnError::Failed { .. } => {
+                "Run with --verbose for details".to_string()
+            }
+        }
+    }
+}
+
+impl fmt::Display for VpnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VpnError::VpnNotFound { vpn } => write!(f, "VPN connection '{}' not found", vpn),
+            VpnError::AuthFailed { vpn } => write!(f, "Authentication failed for VPN '{}'", vpn),
+            VpnError::SecretsRequired { vpn } => {
+                write!(f, "VPN '{}' needs a password but none was provided", vpn)
+            }
+            VpnError::BackendMissing { backend } => {
+                write!(f, "VPN client '{}' is not installed", backend)
+            }
+            VpnError::BackendFailed { backend, message } => {
+                write!(f, "{} failed: {}", backend, message)
+            }
+            VpnError::Failed { vpn, message } => {
+                write!(f, "Failed to connect VPN '{}': {}", vpn, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for VpnError {}
+
+/// Build the VPN backend configured for a server.
+pub fn backend_for(server: &Server) -> Box<dyn VpnBackend> {
+    match &server.vpn_backend {
+        None => default_backend(&server.vpn),
+        Some(VpnBackendConfig::Nmcli) => Box::new(Nmcli::new(&server.vpn)),
+        Some(VpnBackendConfig::Rasphone) => Box::new(Rasphone::new(&server.vpn)),
+        Some(VpnBackendConfig::OpenVpn { config, args }) => Box::new(OpenVpn {
+            name: server.vpn.clone(),
+            config: config.clone(),
+            args: args.clone(),
+        }),
+        Some(VpnBackendConfig::WgQuick { interface }) => Box::new(WgQuick {
+            interface: interface.clone().unwrap_or_else(|| server.vpn.clone()),
+        }),
+        Some(VpnBackendConfig::OpenConnect {
+            server: gateway,
+            user,
+            protocol,
+            args,
+        }) => Box::new(OpenConnect {
+            name: server.vpn.clone(),
+            server: gateway.clone(),
+            user: user.clone(),
+            protocol: protocol.clone(),
+            args: args.clone(),
+        }),
+    }
+}
+
+/// The platform default backend for a system-managed VPN connection.
+#[cfg(windows)]
+pub fn default_backend(vpn_name: &str) -> Box<dyn VpnBackend> {
+    Box::new(Rasphone::new(vpn_name))
+}
+
+#[cfg(not(windows))]
+pub fn default_backend(vpn_name: &str) -> Box<dyn VpnBackend> {
+    Box::new(Nmcli::new(vpn_name))
+}
+
+/// NetworkManager connection controlled through `nmcli`.
+pub struct Nmcli {
+    name: String,
+}
+
+impl Nmcli {
+    /// Create a backend for the named NetworkManager connection.
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+        }
+    }
+}
+
+impl VpnBackend for Nmcli {
+    fn name(&self) -> &'static str {
+        "nmcli"
+    }
+
+    fn connect(&self) -> Result<()> {
+        debug!("Attempting VPN connection via nmcli: {}", self.name);
+
+        let output = Command::new("nmcli")
+            .args(["connection", "up", &self.name])
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => VpnError::BackendMissing { backend: "nmcli" },
+                _ => VpnError::Failed {
+                    vpn: self.name.clone(),
+                    message: e.to_string(),
+                },
+            })?;
+
+        if output.status.success() {
+            debug!("VPN connected via nmcli");
+            return Ok(());
+        }
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        debug!("nmcli exited with {}: {}", output.status, stderr.trim());
+        Err(nmcli_error(&self.name, output.status.code(), &stderr).into())
+    }
+
+    fn disconnect(&self) -> Result<()> {
+        debug!("Disconnecting VPN via nmcli: {}", self.name);
+
+        let result = Command::new("nmcli")
+            .args(["connection", "down", &self.name])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+
+        match result {
+            Ok(status) if status.success() => {
+                debug!("VPN disconnected via nmcli");
+            }
+            Ok(_) => {
+                warn!("nmcli disconnection returned non-zero status");
+            }
+            Err(e) => {
+                debug!("nmcli not available: {}", e);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn is_active(&self) -> Result<bool> {
+        let active = nmcli_connections(true)?;
+        Ok(active.contains(&self.name))
+    }
+}
+
+/// Classify a failed `nmcli connection up` by exit code and stderr.
+fn nmcli_error(vpn: &str, code: Option<i32>, stderr: &str) -> VpnError {
+    let vpn = vpn.to_string();
+    let message = stderr.to_lowercase();
+
+    // Exit code 10: connection, device or access point does not exist
+    if code == Some(10) || message.contains("unknown connection") {
+        VpnError::VpnNotFound { vpn }
+    } else if message.contains("secrets were required") || message.contains("no secrets") {
+        VpnError::SecretsRequired { vpn }
+    } else if message.contains("authentication") || message.contains("login failed") {
+        VpnError::AuthFailed { vpn }
+    } else {
+        let message = stderr
+            .trim()
+            .trim_start_matches("Error: ")
+            .lines()
+            .next()
+            .unwrap_or("")
+            .to_string();
+        VpnError::Failed {
+            vpn,
+            message: match code {
+                Some(code) if message.is_empty() => format!("nmcli exited with code {}", code),
+                _ => message,
+            },
+        }
+    }
+}
+
+/// Names of the NetworkManager connections, or only of the active ones.
+pub fn nmcli_connections(active_only: bool) -> Result<Vec<String>> {
+    let mut args = vec!["-t", "-f", "NAME", "connection", "show"];
+    if active_only {
+        args.push("--active");
+    }
+
+    let output = Command::new("nmcli")
+        .args(&args)
+        .output()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => VpnError::BackendMissing { backend: "nmcli" },
+            _ => VpnError::BackendFailed {
+                backend: "nmcli",
+                message: e.to_string(),
+            },
+        })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(VpnError::BackendFailed {
+            backend: "nmcli",
+            message: failure_message("nmcli", output.status.code(), &stderr),
+        }
+        .into());
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(nmcli_names(&stdout).collect())
+}
+
+/// Connection names from `nmcli -t -f NAME connection show`.
+///
+/// Terse mode escapes colons and backslashes with a backslash.
+fn nmcli_names(output: &str) -> impl Iterator<Item = String> + '_ {
+    output.lines().filter(|line| !line.is_empty()).map(|line| {
+        let mut name = String::with_capacity(line.len());
+        let mut chars = line.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => name.extend(chars.next()),
+                c => name.push(c),
+            }
+        }
+        name
+    })
+}
+
+/// Windows RAS phonebook entry controlled through `rasphone`.
+pub struct Rasphone {
+    name: String,
+}
+
+impl Rasphone {
+    /// Create a backend for the named phonebook entry.
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+        }
+    }
+}
+
+impl VpnBackend for Rasphone {
+    fn name(&self) -> &'static str {
+        "rasphone"
+    }
+
+    fn connect(&self) -> Result<()> {
+        debug!("Executing: rasphone -d {}", self.name);
+
+        Command::new("rasphone")
+            .args(["-d", &self.name])
+            .spawn()
+            .context("Failed to execute rasphone for VPN connection")?;
+
+        Ok(())
+    }
+
+    fn disconnect(&self) -> Result<()> {
+        debug!("Executing: rasphone -h {}", self.name);
+
+        Command::new("rasphone")
+            .args(["-h", &self.name])
+            .spawn()
+            .context("Failed to execute rasphone for VPN disconnection")?;
+
+        Ok(())
+    }
+
+    fn is_active(&self) -> Result<bool> {
+        let output = Command::new("rasdial")
+            .output()
+            .context("Failed to execute rasdial")?;
+
+        // Don't read an error message as a list of connections
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        if !output.status.success() {
+            return Err(VpnError::BackendFailed {
+                backend: "rasdial",
+                message: failure_message("rasdial", output.status.code(), &stdout),
+            }
+            .into());
+        }
+
+        let active = rasdial_connections(&stdout)
+            .iter()
+            .any(|name| name.eq_ignore_ascii_case(&self.name));
+        Ok(active)
+    }
+}
+
+/// Connection names from the listing printed by `rasdial` without arguments.
+///
+/// The names sit between the "Connected to" header and the trailing status
+/// line; with no connections only a status line is printed.
+fn rasdial_connections(output: &str) -> Vec<&str> {
+    let lines: Vec<&str> = output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+    match lines.len() {
+        0..=2 => Vec::new(),
+        n => lines[1..n - 1].to_vec(),
+    }
+}
+
+/// OpenVPN client started as a daemon from a config file.
+pub struct OpenVpn {
+    name: String,
+    config: PathBuf,
+    args: Vec<String>,
+}
+
+impl VpnBackend for OpenVpn {
+    fn name(&self) -> &'static str {
+        "openvpn"
+    }
+
+    fn connect(&self) -> Result<()> {
+        let pid_file = pid_file_path(&self.name)?;
+        debug!(
+            "Executing: openvpn --config {} --daemon --writepid {}",
+            self.config.display(),
+            pid_file.display()
+        );
+
+        let mut command = Command::new("openvpn");
+        command
+            .arg("--config")
+            .arg(&self.config)
+            .arg("--daemon")
+            .arg("--writepid")
+            .arg(&pid_file)
+            .args(&self.args);
+
+        let (status, output) = run_client(&mut command, "openvpn", &self.name)?;
+        if !status.success() {
+            debug!("openvpn exited with {}", status);
+            return Err(openvpn_error(&self.name, status.code(), &output).into());
+        }
+
+        Ok(())
+    }
+
+    fn disconnect(&self) -> Result<()> {
+        stop_pid_file(&pid_file_path(&self.name)?, "openvpn")
+    }
+
+    fn is_active(&self) -> Result<bool> {
+        Ok(running_pid(&pid_file_path(&self.name)?, "openvpn").is_some())
+    }
+}
+
+/// WireGuard interface controlled through `wg-quick`.
+pub struct WgQuick {
+    interface: String,
+}
+
+impl VpnBackend for WgQuick {
+    fn name(&self) -> &'static str {
+        "wg-quick"
+    }
+
+    fn connect(&self) -> Result<()> {
+        debug!("Executing: wg-quick up {}", self.interface);
+
+        let mut command = Command::new("wg-quick");
+        command.args(["up", &self.interface]);
+
+        let (status, output) = run_client(&mut command, "wg-quick", &self.interface)?;
+        if !status.success() {
+            debug!("wg-quick up exited with {}", status);
+            return Err(VpnError::Failed {
+                vpn: self.interface.clone(),
+                message: failure_message("wg-quick", status.code(), &output),
+            }
+            .into());
+        }
+
+        Ok(())
+    }
+
+    fn disconnect(&self) -> Result<()> {
+        debug!("Executing: wg-quick down {}", self.interface);
+
+        let status = Command::new("wg-quick")
+            .args(["down", &self.interface])
+            .stdout(Stdio::null())
+            .status()
+            .context("Failed to execute wg-quick")?;
+
+        if !status.success() {
+            warn!("wg-quick down returned non-zero status");
+        }
+
+        Ok(())
+    }
+
+    fn is_active(&self) -> Result<bool> {
+        // wg-quick creates a network interface named after the config; only
+        // Linux lists interfaces in sysfs, elsewhere wg has to be asked
+        if cfg!(target_os = "linux") {
+            return Ok(Path::new("/sys/class/net").join(&self.interface).exists());
+        }
+
+        debug!("Executing: wg show {}", self.interface);
+        let status = Command::new("wg")
+            .args(["show", &self.interface])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .context("Failed to execute wg")?;
+        Ok(status.success())
+    }
+}
+
+/// OpenConnect (AnyConnect/GlobalProtect/...) client started in the background.
+pub struct OpenConnect {
+    name: String,
+    server: String,
+    user: Option<String>,
+    protocol: Option<String>,
+    args: Vec<String>,
+}
+
+impl VpnBackend for OpenConnect {
+    fn name(&self) -> &'static str {
+        "openconnect"
+    }
+
+    fn connect(&self) -> Result<()> {
+        let pid_file = pid_file_path(&self.name)?;
+
+        let mut command = Command::new("openconnect");
+        command
+            .arg("--background")
+            .arg(format!("--pid-file={}", pid_file.display()));
+        if let Some(user) = &self.user {
+            command.arg(format!("--user={}", user));
+        }
+        if let Some(protocol) = &self.protocol {
+            command.arg(format!("--protocol={}", protocol));
+        }
+        command.args(&self.args).arg(&self.server);
+
+        debug!("Executing: {:?}", command);
+
+        let (status, output) = run_client(&mut command, "openconnect", &self.name)?;
+        if !status.success() {
+            debug!("openconnect exited with {}", status);
+            return Err(openconnect_error(&self.name, status.code(), &output).into());
+        }
+
+        Ok(())
+    }
+
+    fn disconnect(&self) -> Result<()> {
+        stop_pid_file(&pid_file_path(&self.name)?, "openconnect")
+    }
+
+    fn is_active(&self) -> Result<bool> {
+        Ok(running_pid(&pid_file_path(&self.name)?, "openconnect").is_some())
+    }
+}
+
+/// How long to wait for more output once a VPN client has exited.
+const OUTPUT_GRACE: Duration = Duration::from_millis(100);
+
+/// Run a VPN client until it exits (or daemonizes), keeping a copy of its output.
+///
+/// The output is still passed through, since clients may prompt for a
+/// password. A daemon can hold the pipes open after the client itself
+/// exited, so the copy only covers what arrived by then.
+fn run_client(
+    command: &mut Command,
+    backend: &'static str,
+    vpn: &str,
+) -> Result<(ExitStatus, String), VpnError> {
+    let failed = |e: std::io::Error| match e.kind() {
+        std::io::ErrorKind::NotFound => VpnError::BackendMissing { backend },
+        _ => VpnError::Failed {
+            vpn: vpn.to_string(),
+            message: format!("{} failed: {}", backend, e),
+        },
+    };
+
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(failed)?;
+
+    let (tx, rx) = mpsc::channel();
+    if let Some(stdout) = child.stdout.take() {
+        tee(stdout, std::io::stdout(), tx.clone());
+    }
+    if let Some(stderr) = child.stderr.take() {
+        tee(stderr, std::io::stderr(), tx);
+    }
+
+    let status = child.wait().map_err(failed)?;
+    let mut output = Vec::new();
+    while let Ok(chunk) = rx.recv_timeout(OUTPUT_GRACE) {
+        output.extend(chunk);
+    }
+
+    Ok((status, String::from_utf8_lossy(&output).into_owned()))
+}
+
+/// Copy `from` to `to` on a background thread, also sending each chunk to `copy`.
+fn tee(
+    mut from: impl Read + Send + 'static,
+    mut to: impl Write + Send + 'static,
+    copy: mpsc::Sender<Vec<u8>>,
+) {
+    std::thread::spawn(move || {
+        let mut buffer = [0; 1024];
+        while let Ok(n @ 1..) = from.read(&mut buffer) {
+            let _ = to.write_all(&buffer[..n]);
+            let _ = to.flush();
+            // Nobody listens once the client has exited; keep passing through
+            let _ = copy.send(buffer[..n].to_vec());
+        }
+    });
+}
+
+/// Classify a failed `openvpn` start by its output.
+fn openvpn_error(vpn: &str, code: Option<i32>, output: &str) -> VpnError {
+    let message = output.to_lowercase();
+
+    if message.contains("auth_failed")
+        || message.contains("could not read auth")
+        || message.contains("private key password verification failed")
+    {
+        VpnError::AuthFailed {
+            vpn: vpn.to_string(),
+        }
+    } else {
+        VpnError::Failed {
+            vpn: vpn.to_string(),
+            message: failure_message("openvpn", code, output),
+        }
+    }
+}
+
+/// Classify a failed `openconnect` start by exit code and output.
+fn openconnect_error(vpn: &str, code: Option<i32>, output: &str) -> VpnError {
+    let message = output.to_lowercase();
+
+    // Exit code 2: the server rejected the login cookie
+    if code == Some(2)
+        || message.contains("login failed")
+        || message.contains("failed to obtain webvpn cookie")
+        || message.contains("authentication failed")
+    {
+        VpnError::AuthFailed {
+            vpn: vpn.to_string(),
+        }
+    } else {
+        VpnError::Failed {
+            vpn: vpn.to_string(),
+            message: failure_message("openconnect", code, output),
+        }
+    }
+}
+
+/// The line of a client's output that best explains its failure: the first
+/// one mentioning an error, else the last one, else the exit code.
+fn failure_message(backend: &str, code: Option<i32>, output: &str) -> String {
+    let lines = output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty());
+    let line = lines
+        .clone()
+        .find(|line| line.to_lowercase().contains("error"))
+        .or_else(|| lines.clone().next_back());
+
+    match (line, code) {
+        (Some(line), _) => line.trim_start_matches("Error: ").to_string(),
+        (None, Some(code)) => format!("{} exited with code {}", backend, code),
+        (None, None) => format!("{} was terminated", backend),
+    }
+}
+
+/// Directory holding the pid files of daemonized VPN clients.
+///
+/// A per-user runtime (or cache) directory readable only by the user, so
+/// nobody else can plant a pid file for sap_it to act on.
+fn pid_dir() -> Result<PathBuf> {
+    let dir = dirs::runtime_dir()
+        .or_else(dirs::cache_dir)
+        .context("No runtime or cache directory for VPN pid files")?
+        .join("sap_it");
+    create_private_dir(&dir)
+        .with_context(|| format!("Failed to create pid file directory: {}", dir.display()))?;
+    Ok(dir)
+}
+
+#[cfg(unix)]
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+    std::fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(dir)?;
+    // Tighten a directory left with other permissions; fails unless it is ours
+    std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))
+}
+
+#[cfg(not(unix))]
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)
+}
+
+/// Location of the pid file for a daemonized VPN client.
+fn pid_file_path(vpn_name: &str) -> Result<PathBuf> {
+    Ok(pid_dir()?.join(pid_file_name(vpn_name)))
+}
+
+/// File name of a VPN's pid file.
+///
+/// Characters other than ASCII letters, digits and '-' are percent-encoded,
+/// so distinct VPN names never share a file.
+fn pid_file_name(vpn_name: &str) -> String {
+    let mut name = String::from("vpn-");
+    for byte in vpn_name.bytes() {
+        match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' => name.push(byte as char),
+            _ => name.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    name.push_str(".pid");
+    name
+}
+
+/// The pid recorded in a pid file, if that process is still `program`.
+///
+/// A pid whose process exited may have been reused by an unrelated one,
+/// so the program name is checked before the pid is trusted.
+fn running_pid(pid_file: &Path, program: &str) -> Option<u32> {
+    let content = std::fs::read_to_string(pid_file).ok()?;
+    let pid: u32 = match content.trim().parse() {
+        Ok(pid) => pid,
+        Err(_) => {
+            warn!("Ignoring invalid pid file: {}", pid_file.display());
+            return None;
+        }
+    };
+
+    match super::process_name(pid) {
+        Some(name) if name == program => Some(pid),
+        Some(name) => {
+            debug!("pid {} is now {}, not {}", pid, name, program);
+            None
+        }
+        None => {
+            debug!("pid {} from {} is not running", pid, pid_file.display());
+            None
+        }
+    }
+}
+
+/// Stop the `program` process recorded in a pid file and remove the file.
+///
+/// A stale pid file, whose process is gone or is another program, is only
+/// removed.
+fn stop_pid_file(pid_file: &Path, program: &str) -> Result<()> {
+    if !pid_file.exists() {
+        debug!("No pid file at {}", pid_file.display());
+        return Ok(());
+    }
+
+    match running_pid(pid_file, program) {
+        Some(pid) => super::kill_process(pid)?,
+        None => warn!(
+            "{} is not running any more, removing stale pid file {}",
+            program,
+            pid_file.display()
+        ),
+    }
+    let _ = std::fs::remove_file(pid_file);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server_with(backend: Option<VpnBackendConfig>) -> Server {
+        Server {
+            name: "Test".to_string(),
+            rdp: "192.168.1.2".parse().unwrap(),
+            vpn: "TEST VPN".to_string(),
+            vpn_backend: backend,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_backend_selection() {
+        let openvpn = server_with(Some(VpnBackendConfig::OpenVpn {
+            config: PathBuf::from("/etc/openvpn/test.ovpn"),
+            args: Vec::new(),
+        }));
+        assert_eq!(backend_for(&openvpn).name(), "openvpn");
+
+        let wg = server_with(Some(VpnBackendConfig::WgQuick { interface: None }));
+        assert_eq!(backend_for(&wg).name(), "wg-quick");
+
+        let nmcli = server_with(Some(VpnBackendConfig::Nmcli));
+        assert_eq!(backend_for(&nmcli).name(), "nmcli");
+    }
+
+    #[test]
+    fn test_nmcli_errors() {
+        assert_eq!(
+            nmcli_error(
+                "ILMATEX",
+                Some(10),
+                "Error: unknown connection 'ILMATEX'.\n"
+            ),
+            VpnError::VpnNotFound {
+                vpn: "ILMATEX".to_string()
+            }
+        );
+        assert_eq!(
+            nmcli_error(
+                "ILMATEX",
+                Some(4),
+                "Error: Connection activation failed: Secrets were required, but not provided.\n"
+            ),
+            VpnError::SecretsRequired {
+                vpn: "ILMATEX".to_string()
+            }
+        );
+        assert_eq!(
+            nmcli_error(
+                "ILMATEX",
+                Some(4),
+                "Error: Connection activation failed: Login failed.\n"
+            ),
+            VpnError::AuthFailed {
+                vpn: "ILMATEX".to_string()
+            }
+        );
+        assert_eq!(
+            nmcli_error(
+                "ILMATEX",
+                Some(4),
+                "Error: Connection activation failed: The VPN service stopped unexpectedly.\n"
+            ),
+            VpnError::Failed {
+                vpn: "ILMATEX".to_string(),
+                message: "Connection activation failed: The VPN service stopped unexpectedly."
+                    .to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_client_errors() {
+        let vpn = || "Customer".to_string();
+        assert_eq!(
+            openvpn_error(
+                "Customer",
+                Some(1),
+                "2024-05-01 10:00:00 AUTH: Received control message: AUTH_FAILED\n"
+            ),
+            VpnError::AuthFailed { vpn: vpn() }
+        );
+        assert_eq!(
+            openvpn_error(
+                "Customer",
+                Some(1),
+                "Options error: In [CMD-LINE]:1: Error opening configuration file: x.ovpn\n\
+                 Use --help for more information.\n"
+            ),
+            VpnError::Failed {
+                vpn: vpn(),
+                message: "Options error: In [CMD-LINE]:1: Error opening configuration file: x.ovpn"
+                    .to_string()
+            }
+        );
+        assert_eq!(
+            openconnect_error(
+                "Customer",
+                Some(1),
+                "POST https://vpn.example.com/\nLogin failed.\n"
+            ),
+            VpnError::AuthFailed { vpn: vpn() }
+        );
+        assert_eq!(
+            openconnect_error("Customer", Some(2), ""),
+            VpnError::AuthFailed { vpn: vpn() }
+        );
+        assert_eq!(
+            openconnect_error("Customer", Some(1), ""),
+            VpnError::Failed {
+                vpn: vpn(),
+                message: "openconnect exited with code 1".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_missing_client() {
+        let mut command = Command::new("sap_it-no-such-vpn-client");
+        assert_eq!(
+            run_client(&mut command, "openvpn", "Customer").unwrap_err(),
+            VpnError::BackendMissing { backend: "openvpn" }
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_client_keeps_output() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "echo starting; echo 'Login failed.' >&2; exit 1"]);
+        let (status, output) = run_client(&mut command, "openconnect", "Customer").unwrap();
+        assert_eq!(status.code(), Some(1));
+        assert!(output.contains("starting") && output.contains("Login failed."));
+    }
+
+    #[test]
+    fn test_nmcli_names() {
+        let output = "Wired connection 1\nILMATEX\nOffice\\: VPN\n";
+        let names: Vec<String> = nmcli_names(output).collect();
+        assert_eq!(names, vec!["Wired connection 1", "ILMATEX", "Office: VPN"]);
+    }
+
+    #[test]
+    fn test_rasdial_connections() {
+        let connected = "Connected to\r\nFRODEXIM\r\nCommand completed successfully.\r\n";
+        assert_eq!(rasdial_connections(connected), vec!["FRODEXIM"]);
+
+        let none = "No connections\r\nCommand completed successfully.\r\n";
+        assert!(rasdial_connections(none).is_empty());
+    }
+
+    #[test]
+    fn test_pid_file_names_are_distinct() {
+        assert_eq!(pid_file_name("ILMATEX-2"), "vpn-ILMATEX-2.pid");
+        assert_eq!(
+            pid_file_name("Industrial Technik/1"),
+            "vpn-Industrial%20Technik%2F1.pid"
+        );
+
+        let names = ["A B", "A_B", "A.B", "A%20B"].map(pid_file_name);
+        for (i, name) in names.iter().enumerate() {
+            assert!(!names[i + 1..].contains(name), "{} is not unique", name);
+        }
+    }
+
+    #[test]
+    fn test_stale_pid_file_is_not_killed() {
+        let dir = tempfile::tempdir().unwrap();
+        let pid_file = dir.path().join("vpn-test.pid");
+
+        // Our own pid is running, but it is not openvpn
+        std::fs::write(&pid_file, format!("{}\n", std::process::id())).unwrap();
+        assert_eq!(running_pid(&pid_file, "openvpn"), None);
+        stop_pid_file(&pid_file, "openvpn").unwrap();
+        assert!(!pid_file.exists());
+
+        std::fs::write(&pid_file, "not a pid").unwrap();
+        assert_eq!(running_pid(&pid_file, "openvpn"), None);
+        assert!(stop_pid_file(&dir.path().join("missing.pid"), "openvpn").is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_pid_dir_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let pid_dir = dir.path().join("sap_it");
+        std::fs::create_dir(&pid_dir).unwrap();
+        std::fs::set_permissions(&pid_dir, std::fs::Permissions::from_mode(0o777)).unwrap();
+
+        create_private_dir(&pid_dir).unwrap();
+        let mode = std::fs::metadata(&pid_dir).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+    }
+}
@@ -1,32 +1,20 @@
 //! Windows-specific implementations.
 
+use super::rdp;
+use crate::config::{RdpClient, RdpConfig};
 use anyhow::{Context, Result};
-use std::process::{Child, Command, Stdio};
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
 use tracing::debug;
 
-/// Connect to a VPN using Windows rasphone.
-pub fn connect_vpn(vpn_name: &str) -> Result<()> {
-    debug!("Executing: rasphone -d {}", vpn_name);
+/// Number of .rdp files generated so far, to give each session its own.
+static RDP_FILES: AtomicU32 = AtomicU32::new(0);
 
-    Command::new("rasphone")
-        .args(["-d", vpn_name])
-        .spawn()
-        .context("Failed to execute rasphone for VPN connection")?;
-
-    Ok(())
-}
-
-/// Disconnect from a VPN using Windows rasphone.
-pub fn disconnect_vpn(vpn_name: &str) -> Result<()> {
-    debug!("Executing: rasphone -h {}", vpn_name);
-
-    Command::new("rasphone")
-        .args(["-h", vpn_name])
-        .spawn()
-        .context("Failed to execute rasphone for VPN disconnection")?;
-
-    Ok(())
-}
+/// How often to check whether mstsc still runs, to delete its .rdp file.
+const RDP_FILE_POLL: Duration = Duration::from_secs(5);
 
 /// Ping a host using Windows ping command.
 pub fn ping_host(host: &str, timeout_ms: u32) -> bool {
@@ -47,28 +35,95 @@ pub fn ping_host(host: &str, timeout_ms: u32) -> bool {
     }
 }
 
-/// Start an RDP session using mstsc.exe.
-pub fn start_rdp(address: &str) -> Result<Child> {
-    debug!("Executing: mstsc.exe /v:{}", address);
+/// Start an RDP session using mstsc.exe, the only client on Windows.
+///
+/// Options mstsc can't take on the command line are written to a .rdp file
+/// in the temp directory, one per session, deleted once mstsc has exited.
+pub fn start_rdp(rdp: &RdpConfig, _preferred: RdpClient) -> Result<Child> {
+    let rdp_file = if rdp::needs_rdp_file(rdp) {
+        Some(write_rdp_file(rdp)?)
+    } else {
+        None
+    };
+
+    let args = rdp::mstsc_args(rdp, rdp_file.as_deref());
+    debug!("Executing: mstsc.exe {}", args.join(" "));
+
+    let child = Command::new("mstsc.exe").args(args).spawn();
+    if let Some(path) = rdp_file {
+        match &child {
+            Ok(child) => remove_after_exit(child.id(), path),
+            Err(_) => {
+                let _ = fs::remove_file(&path);
+            }
+        }
+    }
+    child.context("Failed to start mstsc.exe")
+}
+
+/// Write the .rdp file for a new session, under a name no other session uses.
+fn write_rdp_file(rdp: &RdpConfig) -> Result<PathBuf> {
+    let sequence = RDP_FILES.fetch_add(1, Ordering::Relaxed);
+    let path = std::env::temp_dir().join(format!("sap_it-{}-{}.rdp", std::process::id(), sequence));
+    fs::write(&path, rdp::rdp_file_contents(rdp))
+        .with_context(|| format!("Failed to write RDP file: {}", path.display()))?;
+    Ok(path)
+}
+
+/// Delete `path`, which holds the username and gateway, once the mstsc
+/// process `pid` has exited.
+fn remove_after_exit(pid: u32, path: PathBuf) {
+    std::thread::spawn(move || {
+        while process_name(pid).is_some_and(|name| name.eq_ignore_ascii_case("mstsc")) {
+            std::thread::sleep(RDP_FILE_POLL);
+        }
+        debug!("Removing RDP file {}", path.display());
+        let _ = fs::remove_file(&path);
+    });
+}
+
+/// Start an SSH session using the ssh command and wait for it to end.
+///
+/// Returns the exit status and the end of ssh's error output.
+pub fn start_ssh(args: &[String]) -> Result<(ExitStatus, String)> {
+    debug!("Executing: ssh {}", args.join(" "));
 
-    Command::new("mstsc.exe")
-        .arg(format!("/v:{}", address))
-        .spawn()
-        .context("Failed to start mstsc.exe")
+    super::ssh::run_interactive(Command::new("ssh").args(args))
 }
 
-/// Start an SSH session using the ssh command.
-pub fn start_ssh(target: &str) -> Result<()> {
-    debug!("Executing: ssh {}", target);
+/// Terminate a process using taskkill.
+pub fn kill_process(pid: u32) -> Result<()> {
+    debug!("Executing: taskkill /PID {} /F", pid);
 
-    Command::new("ssh")
-        .arg(target)
+    let status = Command::new("taskkill")
+        .args(["/PID", &pid.to_string(), "/F"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
         .status()
-        .context("Failed to execute ssh")?;
+        .context("Failed to execute taskkill")?;
+
+    if !status.success() {
+        anyhow::bail!("Failed to terminate process {}", pid);
+    }
 
     Ok(())
 }
 
+/// Name of the program running as `pid`, or None if there is no such process.
+pub fn process_name(pid: u32) -> Option<String> {
+    let output = Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/FO", "CSV", "/NH"])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+
+    // "openvpn.exe","1234",... or an INFO line when nothing matches
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let image = stdout.lines().next()?.split(',').next()?;
+    let image = image.strip_prefix('"')?.strip_suffix('"')?;
+    Some(image.trim_end_matches(".exe").to_string())
+}
+
 /// Clear the terminal screen.
 pub fn clear_screen() {
     let _ = Command::new("cmd").args(["/c", "cls"]).status();
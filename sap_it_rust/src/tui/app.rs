@@ -1,12 +1,27 @@
 //! Application state for the TUI.
 
-use crate::config::{Config, Server};
-use crate::connection::ConnectionType;
-use crate::platform;
+use crate::config::{Config, RdpConfig, Server, Settings, SshConfig, SshMode};
+use crate::config_file::ConfigFile;
+use crate::connection::{check_ssh_exit, ConnectError, ConnectEvent, ConnectionType};
+use crate::platform::{self, vpn};
+use crate::tui::connect::TaskMessage;
+use crate::tui::history::{Entry, History};
+use crate::tui::pty::PtySession;
+use crate::tui::search;
+use crate::tui::server_form::{self, FieldErrors};
+use crate::tui::session::{Session, SessionId, SessionRegistry};
+use crate::tui::settings::SettingField;
+use crate::tui::status::{ServerStatus, StatusWorker};
+use crate::validate::Issue;
+use std::collections::HashSet;
+use std::path::PathBuf;
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+/// Number of fields in the add/edit server form.
+pub const EDIT_FIELD_COUNT: usize = 6;
+
 /// Current screen/view in the application.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Screen {
@@ -26,6 +41,8 @@ pub enum Screen {
     EditServer,
     /// Confirmation dialog
     Confirm,
+    /// Active sessions list
+    Sessions,
 }
 
 /// Connection status during the connection process.
@@ -39,15 +56,26 @@ pub enum ConnectionStatus {
     StartingSession,
     Connected,
     Disconnecting,
-    Error(String),
+    Error(ConnectError),
+}
+
+/// A row of the server list tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListRow {
+    /// Group header (None for servers without a group).
+    Group(Option<String>),
+    /// Server, by index into `config.servers`.
+    Server(usize),
 }
 
 /// Confirmation dialog type.
 #[derive(Debug, Clone)]
 pub enum ConfirmAction {
     DeleteServer(usize),
-    Disconnect,
+    Disconnect(SessionId),
     Quit,
+    /// Save unsaved configuration changes before quitting.
+    SaveChanges,
 }
 
 /// Application state.
@@ -55,6 +83,21 @@ pub struct App {
     /// Current configuration.
     pub config: Config,
 
+    /// File the configuration was loaded from and is saved to.
+    pub config_path: PathBuf,
+
+    /// Whether the configuration has changes not yet saved.
+    pub modified: bool,
+
+    /// The configuration as last loaded or saved, serialized.
+    saved_config: String,
+
+    /// Undo/redo history of configuration changes.
+    pub history: History,
+
+    /// Description of the last change, undo or redo, for the status bar.
+    pub last_action: Option<String>,
+
     /// Current screen.
     pub screen: Screen,
 
@@ -64,12 +107,27 @@ pub struct App {
     /// Selected server index.
     pub selected_server: usize,
 
+    /// Selected row in the server list tree.
+    pub selected_row: usize,
+
+    /// Groups collapsed in the server list.
+    pub collapsed_groups: HashSet<Option<String>>,
+
+    /// Whether keystrokes go to the search query.
+    pub search_active: bool,
+
+    /// Fuzzy search query filtering the server list.
+    pub search_query: String,
+
+    /// Last known reachability of each server, by config index.
+    server_status: Vec<ServerStatus>,
+
+    /// Background reachability prober (None when disabled).
+    status_worker: Option<StatusWorker>,
+
     /// Selected connection type index.
     pub selected_conn_type: usize,
 
-    /// Current connection status.
-    pub connection_status: ConnectionStatus,
-
     /// Status messages log.
     pub status_log: Vec<(Instant, String)>,
 
@@ -80,14 +138,20 @@ pub struct App {
     #[allow(dead_code)]
     pub shutdown_flag: Arc<AtomicBool>,
 
-    /// Currently connected server (if any).
-    pub connected_server: Option<usize>,
+    /// Active and failed connection sessions.
+    pub sessions: SessionRegistry,
 
-    /// Connected VPN name (for disconnection).
-    pub connected_vpn: Option<String>,
+    /// Session shown in the Connecting/Connected screens.
+    pub active_session: Option<SessionId>,
 
-    /// Connection start time.
-    pub connection_start: Option<Instant>,
+    /// Selected row in the Sessions screen.
+    pub selected_session: usize,
+
+    /// Session whose SSH client should be run in the terminal.
+    ssh_requested: Option<SessionId>,
+
+    /// Whether keystrokes go to the active session's embedded terminal.
+    pub pty_focused: bool,
 
     /// Confirmation dialog action.
     pub confirm_action: Option<ConfirmAction>,
@@ -104,14 +168,26 @@ pub struct App {
     /// Currently editing field index.
     pub edit_field_index: usize,
 
+    /// Whether saving the form was attempted, so empty required fields are flagged.
+    pub edit_submitted: bool,
+
+    /// NetworkManager connections the VPN field is checked against, if known.
+    known_vpns: Option<Vec<String>>,
+
     /// Input buffer for text editing.
     pub input_buffer: String,
 
-    /// Cursor position in input.
+    /// Cursor position in input, in characters.
     pub cursor_position: usize,
 
-    /// Settings scroll position.
-    pub settings_scroll: usize,
+    /// Selected field in the Settings screen.
+    pub selected_setting: usize,
+
+    /// Whether the selected setting is being typed into `input_buffer`.
+    pub settings_editing: bool,
+
+    /// Why the typed setting was rejected.
+    pub settings_error: Option<String>,
 
     /// Help scroll position.
     pub help_scroll: usize,
@@ -124,34 +200,83 @@ pub struct EditServerFields {
     pub rdp: String,
     pub ssh: String,
     pub vpn: String,
+    pub group: String,
+    pub tags: String,
+}
+
+impl EditServerFields {
+    /// Field by form index.
+    pub fn get(&self, index: usize) -> Option<&String> {
+        match index {
+            0 => Some(&self.name),
+            1 => Some(&self.rdp),
+            2 => Some(&self.ssh),
+            3 => Some(&self.vpn),
+            4 => Some(&self.group),
+            5 => Some(&self.tags),
+            _ => None,
+        }
+    }
+
+    /// Mutable field by form index.
+    fn get_mut(&mut self, index: usize) -> Option<&mut String> {
+        match index {
+            0 => Some(&mut self.name),
+            1 => Some(&mut self.rdp),
+            2 => Some(&mut self.ssh),
+            3 => Some(&mut self.vpn),
+            4 => Some(&mut self.group),
+            5 => Some(&mut self.tags),
+            _ => None,
+        }
+    }
 }
 
 impl App {
-    /// Create a new application with the given configuration.
-    pub fn new(config: Config) -> Self {
+    /// Create a new application with the configuration loaded from `config_path`.
+    pub fn new(config: Config, config_path: PathBuf) -> Self {
         let shutdown_flag = Arc::new(AtomicBool::new(false));
 
+        let status_worker = spawn_status_worker(&config);
+        let saved_config = serialized(&config);
+
         Self {
             config,
+            config_path,
+            modified: false,
+            saved_config,
+            history: History::default(),
+            last_action: None,
             screen: Screen::ServerList,
             prev_screen: None,
             selected_server: 0,
+            selected_row: 0,
+            collapsed_groups: HashSet::new(),
+            search_active: false,
+            search_query: String::new(),
+            server_status: Vec::new(),
+            status_worker,
             selected_conn_type: 0,
-            connection_status: ConnectionStatus::Idle,
             status_log: Vec::new(),
             should_quit: false,
             shutdown_flag,
-            connected_server: None,
-            connected_vpn: None,
-            connection_start: None,
+            sessions: SessionRegistry::default(),
+            active_session: None,
+            selected_session: 0,
+            ssh_requested: None,
+            pty_focused: false,
             confirm_action: None,
             confirm_selection: 0,
             edit_server_fields: EditServerFields::default(),
             edit_mode: false,
             edit_field_index: 0,
+            edit_submitted: false,
+            known_vpns: None,
             input_buffer: String::new(),
             cursor_position: 0,
-            settings_scroll: 0,
+            selected_setting: 0,
+            settings_editing: false,
+            settings_error: None,
             help_scroll: 0,
         }
     }
@@ -165,9 +290,172 @@ impl App {
         }
     }
 
-    /// Get the currently selected server.
+    /// Get the currently selected server (None while a group header is
+    /// selected or the search matches nothing).
     pub fn current_server(&self) -> Option<&Server> {
-        self.config.servers.get(self.selected_server)
+        match self.server_rows().get(self.selected_row) {
+            Some(ListRow::Server(_)) => self.config.servers.get(self.selected_server),
+            _ => None,
+        }
+    }
+
+    /// Get the last known reachability of a server.
+    pub fn server_status(&self, index: usize) -> ServerStatus {
+        self.server_status.get(index).copied().unwrap_or_default()
+    }
+
+    /// Apply reachability results from the background worker.
+    pub fn poll_status(&mut self) {
+        if let Some(worker) = &mut self.status_worker {
+            worker.poll(&mut self.server_status);
+        }
+    }
+
+    /// Restart reachability probing after servers were added, changed or removed.
+    fn refresh_status(&mut self) {
+        self.server_status.clear();
+        if let Some(worker) = &mut self.status_worker {
+            worker.update(&self.config);
+        }
+    }
+
+    /// Rows of the server list: search matches (best first) while searching,
+    /// flat when no server has a group, otherwise group headers followed by
+    /// the servers of expanded groups.
+    pub fn server_rows(&self) -> Vec<ListRow> {
+        if !self.search_query.is_empty() {
+            return search::filter_servers(&self.config.servers, &self.search_query)
+                .into_iter()
+                .map(|m| ListRow::Server(m.index))
+                .collect();
+        }
+
+        if !self.config.servers.iter().any(|s| s.group_name().is_some()) {
+            return (0..self.config.servers.len())
+                .map(ListRow::Server)
+                .collect();
+        }
+
+        let mut rows = Vec::new();
+        for (group, indices) in self.config.grouped_servers() {
+            let group = group.map(str::to_string);
+            let collapsed = self.collapsed_groups.contains(&group);
+            rows.push(ListRow::Group(group));
+            if !collapsed {
+                rows.extend(indices.into_iter().map(ListRow::Server));
+            }
+        }
+        rows
+    }
+
+    /// Select a row of the server list, tracking the server it refers to.
+    fn select_row(&mut self, row: usize) {
+        let rows = self.server_rows();
+        self.selected_row = row.min(rows.len().saturating_sub(1));
+        if let Some(ListRow::Server(index)) = rows.get(self.selected_row) {
+            self.selected_server = *index;
+        }
+    }
+
+    /// Select a server by config index, expanding its group if needed.
+    pub fn select_server(&mut self, index: usize) {
+        if let Some(server) = self.config.servers.get(index) {
+            let group = server.group_name().map(str::to_string);
+            self.collapsed_groups.remove(&group);
+        }
+        self.selected_server = index;
+        let position = |app: &Self| {
+            app.server_rows()
+                .iter()
+                .position(|row| *row == ListRow::Server(index))
+        };
+        let row = match position(self) {
+            Some(row) => row,
+            None => {
+                // Not among the search results, show the full list again
+                self.search_query.clear();
+                position(self).unwrap_or(0)
+            }
+        };
+        self.select_row(row);
+    }
+
+    /// Start typing a search query.
+    pub fn start_search(&mut self) {
+        self.search_active = true;
+    }
+
+    /// Stop typing but keep the current filter.
+    pub fn finish_search(&mut self) {
+        self.search_active = false;
+    }
+
+    /// Leave search mode and show the full list, keeping the selected server.
+    pub fn clear_search(&mut self) {
+        self.search_active = false;
+        if !self.search_query.is_empty() {
+            let selected = self.current_server().map(|_| self.selected_server);
+            self.search_query.clear();
+            match selected {
+                Some(index) => self.select_server(index),
+                None => self.select_row(0),
+            }
+        }
+    }
+
+    /// Append a character to the search query.
+    pub fn search_push(&mut self, c: char) {
+        self.search_query.push(c);
+        self.select_row(0);
+    }
+
+    /// Remove the last character of the search query.
+    pub fn search_pop(&mut self) {
+        self.search_query.pop();
+        self.select_row(0);
+    }
+
+    /// Group of the selected row (the header itself or the server's group).
+    fn selected_group(&self) -> Option<Option<String>> {
+        let rows = self.server_rows();
+        match rows.get(self.selected_row)? {
+            ListRow::Group(group) => Some(group.clone()),
+            // A flat list has no groups to collapse
+            ListRow::Server(_) if !matches!(rows.first(), Some(ListRow::Group(_))) => None,
+            ListRow::Server(index) => {
+                let server = self.config.servers.get(*index)?;
+                Some(server.group_name().map(str::to_string))
+            }
+        }
+    }
+
+    /// Collapse the selected group and move the selection to its header.
+    pub fn collapse_group(&mut self) {
+        if let Some(group) = self.selected_group() {
+            self.collapsed_groups.insert(group.clone());
+            let row = self
+                .server_rows()
+                .iter()
+                .position(|row| *row == ListRow::Group(group.clone()))
+                .unwrap_or(0);
+            self.select_row(row);
+        }
+    }
+
+    /// Expand the selected group.
+    pub fn expand_group(&mut self) {
+        if let Some(group) = self.selected_group() {
+            self.collapsed_groups.remove(&group);
+        }
+    }
+
+    /// Toggle the group whose header is selected.
+    pub fn toggle_group(&mut self) {
+        if let Some(ListRow::Group(group)) = self.server_rows().get(self.selected_row) {
+            if !self.collapsed_groups.remove(group) {
+                self.collapsed_groups.insert(group.clone());
+            }
+        }
     }
 
     /// Get the selected connection type.
@@ -214,10 +502,11 @@ impl App {
     pub fn select_previous(&mut self) {
         match self.screen {
             Screen::ServerList => {
-                if self.selected_server > 0 {
-                    self.selected_server -= 1;
-                } else if !self.config.servers.is_empty() {
-                    self.selected_server = self.config.servers.len() - 1;
+                let len = self.server_rows().len();
+                if self.selected_row > 0 {
+                    self.select_row(self.selected_row - 1);
+                } else if len > 0 {
+                    self.select_row(len - 1);
                 }
             }
             Screen::ConnectionTypeSelect => {
@@ -231,17 +520,22 @@ impl App {
             Screen::Confirm => {
                 self.confirm_selection = if self.confirm_selection == 0 { 1 } else { 0 };
             }
-            Screen::EditServer => {
-                if self.edit_field_index > 0 {
-                    self.edit_field_index -= 1;
-                    self.load_field_to_input();
-                }
+            Screen::EditServer if self.edit_field_index > 0 => {
+                self.edit_field_index -= 1;
+                self.load_field_to_input();
             }
             Screen::Help => {
                 self.help_scroll = self.help_scroll.saturating_sub(1);
             }
-            Screen::Settings => {
-                self.settings_scroll = self.settings_scroll.saturating_sub(1);
+            Screen::Settings if self.selected_setting > 0 => {
+                self.selected_setting -= 1;
+                self.settings_error = None;
+            }
+            Screen::Sessions if !self.sessions.is_empty() => {
+                self.selected_session = self
+                    .selected_session
+                    .checked_sub(1)
+                    .unwrap_or(self.sessions.len() - 1);
             }
             _ => {}
         }
@@ -250,10 +544,9 @@ impl App {
     /// Move selection down in current list.
     pub fn select_next(&mut self) {
         match self.screen {
-            Screen::ServerList => {
-                if !self.config.servers.is_empty() {
-                    self.selected_server = (self.selected_server + 1) % self.config.servers.len();
-                }
+            Screen::ServerList if !self.config.servers.is_empty() => {
+                let len = self.server_rows().len();
+                self.select_row((self.selected_row + 1) % len);
             }
             Screen::ConnectionTypeSelect => {
                 let types = self.available_connection_types();
@@ -262,17 +555,19 @@ impl App {
             Screen::Confirm => {
                 self.confirm_selection = if self.confirm_selection == 0 { 1 } else { 0 };
             }
-            Screen::EditServer => {
-                if self.edit_field_index < 3 {
-                    self.edit_field_index += 1;
-                    self.load_field_to_input();
-                }
+            Screen::EditServer if self.edit_field_index < EDIT_FIELD_COUNT - 1 => {
+                self.edit_field_index += 1;
+                self.load_field_to_input();
             }
             Screen::Help => {
                 self.help_scroll += 1;
             }
-            Screen::Settings => {
-                self.settings_scroll += 1;
+            Screen::Settings if self.selected_setting < SettingField::ALL.len() - 1 => {
+                self.selected_setting += 1;
+                self.settings_error = None;
+            }
+            Screen::Sessions if !self.sessions.is_empty() => {
+                self.selected_session = (self.selected_session + 1) % self.sessions.len();
             }
             _ => {}
         }
@@ -281,162 +576,428 @@ impl App {
     /// Handle enter/confirm action.
     pub fn confirm_selection(&mut self) {
         match self.screen {
-            Screen::ServerList => {
-                if self.current_server().is_some() {
-                    // Check if SSH is available
-                    if self.current_server().map(|s| s.has_ssh()).unwrap_or(false) {
-                        self.selected_conn_type = 0;
-                        self.go_to_screen(Screen::ConnectionTypeSelect);
-                    } else {
-                        // Only RDP available, skip connection type selection
-                        self.selected_conn_type = 0;
-                        self.start_connection();
-                    }
+            Screen::ServerList
+                if matches!(
+                    self.server_rows().get(self.selected_row),
+                    Some(ListRow::Group(_))
+                ) =>
+            {
+                self.toggle_group();
+            }
+            Screen::ServerList if self.current_server().is_some() => {
+                // Check if SSH is available
+                if self.current_server().map(|s| s.has_ssh()).unwrap_or(false) {
+                    self.selected_conn_type = 0;
+                    self.go_to_screen(Screen::ConnectionTypeSelect);
+                } else {
+                    // Only RDP available, skip connection type selection
+                    self.selected_conn_type = 0;
+                    self.start_connection();
                 }
             }
             Screen::ConnectionTypeSelect => {
                 self.start_connection();
             }
             Screen::Confirm => {
-                if self.confirm_selection == 1 {
-                    // Yes selected
-                    if let Some(action) = self.confirm_action.take() {
-                        match action {
-                            ConfirmAction::DeleteServer(index) => {
-                                self.config.servers.remove(index);
-                                if self.selected_server >= self.config.servers.len()
-                                    && self.selected_server > 0
-                                {
-                                    self.selected_server -= 1;
-                                }
-                                self.log_status("Server deleted");
-                            }
-                            ConfirmAction::Disconnect => {
-                                self.disconnect();
-                            }
-                            ConfirmAction::Quit => {
-                                self.disconnect();
-                                self.should_quit = true;
-                            }
+                let yes = self.confirm_selection == 1;
+                let action = self.confirm_action.take();
+                self.go_back();
+                match action {
+                    Some(ConfirmAction::DeleteServer(index)) if yes => {
+                        let before = self.config.clone();
+                        let server = self.config.servers.remove(index);
+                        self.refresh_status();
+                        let next = index.min(self.config.servers.len().saturating_sub(1));
+                        self.select_server(next);
+                        self.config_changed(
+                            before,
+                            index,
+                            format!("Deleted server '{}'", server.name),
+                        );
+                    }
+                    Some(ConfirmAction::Disconnect(id)) if yes => {
+                        self.disconnect_session(id);
+                    }
+                    Some(ConfirmAction::Quit) if yes => {
+                        for id in self.sessions.ids() {
+                            self.disconnect_session(id);
                         }
+                        self.leave_session_screens();
+                        self.quit();
                     }
+                    // "No" quits without saving; a failed save keeps the app open
+                    Some(ConfirmAction::SaveChanges) => {
+                        self.should_quit = !yes || self.save_changes();
+                    }
+                    _ => {}
                 }
-                self.confirm_action = None;
-                self.go_back();
+                self.leave_session_screens();
             }
             Screen::EditServer => {
                 self.save_current_field();
-                if self.edit_field_index < 3 {
+                if self.edit_field_index < EDIT_FIELD_COUNT - 1 {
                     self.edit_field_index += 1;
                     self.load_field_to_input();
+                } else if let Some(invalid) =
+                    self.edit_server_errors().iter().position(Option::is_some)
+                {
+                    // Show every problem and jump to the first one
+                    self.edit_submitted = true;
+                    self.edit_field_index = invalid;
+                    self.load_field_to_input();
+                    self.log_status("Fix the marked fields before saving");
                 } else {
-                    // Save server
                     self.save_server();
                     self.go_to_screen(Screen::ServerList);
                 }
             }
             Screen::Connected => {
-                // Show disconnect confirmation
-                self.confirm_action = Some(ConfirmAction::Disconnect);
-                self.confirm_selection = 0;
-                self.go_to_screen(Screen::Confirm);
+                if let Some(id) = self.active_session {
+                    self.confirm_disconnect(id);
+                }
+            }
+            Screen::Sessions => {
+                if let Some(id) = self.selected_session_id() {
+                    self.switch_to_session(id);
+                }
             }
             _ => {}
         }
     }
 
-    /// Start the connection process.
+    /// Start a session to the selected server in the background.
+    ///
+    /// If the server already has a running session, switch to it instead.
     fn start_connection(&mut self) {
-        if let Some(server) = self.current_server().cloned() {
-            self.connection_status = ConnectionStatus::ConnectingVpn;
-            self.connection_start = Some(Instant::now());
-            self.connected_vpn = Some(server.vpn.clone());
-            self.connected_server = Some(self.selected_server);
-            self.log_status(format!("Connecting to VPN: {}", server.vpn));
-            self.go_to_screen(Screen::Connecting);
-
-            // Start VPN connection
-            if let Err(e) = platform::connect_vpn(&server.vpn) {
-                self.connection_status = ConnectionStatus::Error(format!("VPN error: {}", e));
-                self.log_status(format!("VPN connection failed: {}", e));
-            } else {
-                self.connection_status = ConnectionStatus::WaitingForVpn;
-                self.log_status("Waiting for VPN to establish...");
+        let Some(server) = self.current_server().cloned() else {
+            return;
+        };
+
+        if let Some(id) = self.sessions.find_server(&server.name) {
+            if !self.sessions.get(id).is_some_and(Session::is_finished) {
+                self.log_status(format!("Already connected to {}", server.name));
+                self.switch_to_session(id);
+                return;
             }
+            // Replace the failed session
+            self.sessions.remove(id);
         }
+
+        let conn_type = self.selected_connection_type();
+        self.log_status(format!(
+            "Connecting to {} ({})",
+            server.name,
+            conn_type.name()
+        ));
+        let id = self
+            .sessions
+            .start(server, self.config.settings.clone(), conn_type);
+        self.active_session = Some(id);
+        self.pty_focused = false;
+        self.go_to_screen(Screen::Connecting);
     }
 
-    /// Disconnect from current session.
+    /// The session shown in the Connecting/Connected screens.
+    pub fn active(&self) -> Option<&Session> {
+        self.active_session.and_then(|id| self.sessions.get(id))
+    }
+
+    /// Show a session in the Connecting or Connected screen.
+    pub fn switch_to_session(&mut self, id: SessionId) {
+        let Some(session) = self.sessions.get(id) else {
+            return;
+        };
+        let screen = if session.status == ConnectionStatus::Connected {
+            Screen::Connected
+        } else {
+            Screen::Connecting
+        };
+
+        if self.active_session != Some(id) {
+            self.pty_focused = false;
+        }
+        self.active_session = Some(id);
+        self.go_to_screen(screen);
+    }
+
+    /// Id of the session selected in the Sessions screen.
+    pub fn selected_session_id(&self) -> Option<SessionId> {
+        self.sessions
+            .get_index(self.selected_session)
+            .map(|session| session.id)
+    }
+
+    /// Ask for confirmation before disconnecting a session.
+    pub fn confirm_disconnect(&mut self, id: SessionId) {
+        self.confirm_action = Some(ConfirmAction::Disconnect(id));
+        self.confirm_selection = 0;
+        self.go_to_screen(Screen::Confirm);
+    }
+
+    /// Disconnect the active session.
     pub fn disconnect(&mut self) {
-        if let Some(vpn) = self.connected_vpn.take() {
-            self.connection_status = ConnectionStatus::Disconnecting;
-            self.log_status(format!("Disconnecting VPN: {}", vpn));
-            let _ = platform::disconnect_vpn(&vpn);
-            self.log_status("Disconnected");
-        }
-        self.connected_server = None;
-        self.connection_start = None;
-        self.connection_status = ConnectionStatus::Idle;
-        self.screen = Screen::ServerList;
-    }
-
-    /// Update connection status (called periodically).
-    pub fn update_connection(&mut self) {
-        match &self.connection_status {
-            ConnectionStatus::WaitingForVpn => {
-                if let Some(server) = self.current_server() {
-                    // Check if VPN is connected by pinging the server
-                    if platform::ping_host(&server.rdp, self.config.settings.ping_timeout_ms) {
-                        self.connection_status = ConnectionStatus::StartingSession;
-                        self.log_status("VPN connected, starting session...");
-                    } else if let Some(start) = self.connection_start {
-                        // Check for timeout
-                        if start.elapsed()
-                            > Duration::from_secs(self.config.settings.vpn_timeout_secs)
-                        {
-                            self.connection_status =
-                                ConnectionStatus::Error("VPN connection timeout".to_string());
-                            self.log_status("VPN connection timed out");
-                        }
+        if let Some(id) = self.active_session {
+            self.disconnect_session(id);
+        }
+    }
+
+    /// Disconnect a session.
+    ///
+    /// Returns immediately; the connection task tears down in the background.
+    pub fn disconnect_session(&mut self, id: SessionId) {
+        let Some(session) = self.sessions.get_mut(id) else {
+            return;
+        };
+        let name = session.server.name.clone();
+
+        if session.is_finished() {
+            self.sessions.remove(id);
+        } else {
+            session.cancel();
+            self.log_status(format!("Disconnecting from {}...", name));
+        }
+
+        if self.active_session == Some(id) {
+            self.active_session = None;
+            self.pty_focused = false;
+        }
+        self.leave_session_screens();
+        self.selected_session = self
+            .selected_session
+            .min(self.sessions.len().saturating_sub(1));
+    }
+
+    /// Return to the server list if the shown session is gone.
+    fn leave_session_screens(&mut self) {
+        if self.active().is_none() && matches!(self.screen, Screen::Connecting | Screen::Connected)
+        {
+            self.screen = Screen::ServerList;
+        }
+    }
+
+    /// Apply progress from all connection tasks (called periodically).
+    pub fn update_sessions(&mut self) {
+        for id in self.sessions.ids() {
+            let Some(session) = self.sessions.get_mut(id) else {
+                continue;
+            };
+
+            let exit = session.ssh.as_ref().and_then(PtySession::exit_status);
+            if let (Some(status), Some(pty)) = (exit, session.ssh.take()) {
+                // Judged like the suspended-terminal session, from ssh's output
+                let result = match session.server.ssh_config() {
+                    Some(ssh) => {
+                        check_ssh_exit(ssh, Some(status.exit_code() as i32), &pty.screen_text())
                     }
+                    None => Ok(()),
+                };
+                if self.active_session == Some(id) {
+                    self.pty_focused = false;
                 }
+                self.ssh_finished(id, result.map_err(anyhow::Error::from));
             }
-            ConnectionStatus::StartingSession => {
-                if let Some(server) = self.current_server().cloned() {
-                    let conn_type = self.selected_connection_type();
-
-                    match conn_type {
-                        ConnectionType::Rdp | ConnectionType::Both => {
-                            if let Err(e) = platform::start_rdp(&server.rdp) {
-                                self.log_status(format!("RDP error: {}", e));
-                            } else {
-                                self.log_status(format!("RDP session started to {}", server.rdp));
-                            }
-                        }
-                        _ => {}
-                    }
 
-                    if conn_type == ConnectionType::Ssh || conn_type == ConnectionType::Both {
-                        if let Some(ssh) = server.ssh_string() {
-                            self.log_status(format!("SSH: {}", ssh));
-                        }
-                    }
+            let messages = match self.sessions.get_mut(id) {
+                Some(session) => session.drain_messages(),
+                None => continue,
+            };
+            for message in messages {
+                self.apply_task_message(id, message);
+            }
+        }
+    }
+
+    /// Update a session for a message from its connection task.
+    fn apply_task_message(&mut self, id: SessionId, message: TaskMessage) {
+        let Some(session) = self.sessions.get_mut(id) else {
+            return;
+        };
+        let name = session.server.name.clone();
+
+        match message {
+            TaskMessage::Event(event) => self.handle_connect_event(id, event),
+            TaskMessage::Connected { rdp_pid } => {
+                session.status = ConnectionStatus::Connected;
+                session.rdp_pid = rdp_pid;
+                let conn_type = session.conn_type;
+                self.log_status(format!("{}: Session active", name));
 
-                    self.connection_status = ConnectionStatus::Connected;
+                if self.active_session == Some(id) && self.screen == Screen::Connecting {
                     self.screen = Screen::Connected;
-                    self.log_status("Session active");
+                    if conn_type != ConnectionType::Rdp {
+                        self.open_ssh();
+                    }
                 }
             }
-            _ => {}
+            TaskMessage::Failed(error) => {
+                session.status = ConnectionStatus::Error(error.clone());
+                self.log_status(format!("{}: Connection failed: {}", name, error));
+            }
+            TaskMessage::Finished => {
+                // Failed sessions stay listed until dismissed
+                if !matches!(session.status, ConnectionStatus::Error(_)) {
+                    self.sessions.remove(id);
+                    if self.active_session == Some(id) {
+                        self.active_session = None;
+                        self.pty_focused = false;
+                    }
+                    self.leave_session_screens();
+                    self.selected_session = self
+                        .selected_session
+                        .min(self.sessions.len().saturating_sub(1));
+                }
+                self.log_status(format!("{}: Disconnected", name));
+            }
+        }
+    }
+
+    /// Open an SSH session to the active session's server.
+    ///
+    /// In embedded mode ssh starts in a pane right away; otherwise the main
+    /// loop hands the terminal over (see `take_ssh_request`).
+    pub fn open_ssh(&mut self) {
+        let Some(session) = self.active() else {
+            return;
+        };
+        if session.status != ConnectionStatus::Connected || !session.server.has_ssh() {
+            return;
+        }
+
+        let id = session.id;
+        match self.config.settings.ssh_mode {
+            SshMode::External => self.ssh_requested = Some(id),
+            SshMode::Embedded => self.open_pty(id),
+        }
+    }
+
+    /// Start ssh in a session's embedded terminal pane, or focus a running one.
+    fn open_pty(&mut self, id: SessionId) {
+        let Some(session) = self.sessions.get_mut(id) else {
+            return;
+        };
+        if session.ssh.is_some() {
+            self.pty_focused = true;
+            return;
+        }
+        let Some(ssh) = session.server.ssh_config() else {
+            return;
+        };
+
+        let args = platform::ssh::ssh_args(ssh, session.server.jump_host());
+        let target = ssh.to_string();
+        match PtySession::spawn(&args, 24, 80) {
+            Ok(pty) => {
+                session.ssh = Some(pty);
+                self.pty_focused = true;
+                self.log_status(format!("SSH session to {}", target));
+            }
+            Err(e) => self.log_status(format!("SSH error: {:#}", e)),
         }
     }
 
+    /// The active session's embedded terminal, if open.
+    pub fn pty(&self) -> Option<&PtySession> {
+        self.active().and_then(|session| session.ssh.as_ref())
+    }
+
+    /// Toggle whether keystrokes go to the embedded SSH session.
+    pub fn toggle_pty_focus(&mut self) {
+        self.pty_focused = self.pty().is_some() && !self.pty_focused;
+    }
+
+    /// Take a pending SSH request; the caller runs ssh in the terminal.
+    pub fn take_ssh_request(&mut self) -> Option<(SessionId, Server)> {
+        let id = self.ssh_requested.take()?;
+        let server = self.sessions.get(id)?.server.clone();
+        if let Some(ssh) = server.ssh_string() {
+            self.log_status(format!("SSH session to {}", ssh));
+        }
+        Some((id, server))
+    }
+
+    /// Return to the TUI after a session's SSH client exited.
+    ///
+    /// SSH-only sessions are disconnected; with RDP still running the
+    /// Connected screen is shown again.
+    pub fn ssh_finished(&mut self, id: SessionId, result: anyhow::Result<()>) {
+        match result {
+            Ok(()) => self.log_status("SSH session ended"),
+            Err(e) => self.log_status(format!("SSH error: {:#}", e)),
+        }
+
+        let Some(session) = self.sessions.get(id) else {
+            return;
+        };
+        if session.conn_type == ConnectionType::Ssh {
+            self.disconnect_session(id);
+        } else if self.active_session == Some(id) && !session.is_finished() {
+            self.screen = Screen::Connected;
+        }
+    }
+
+    /// Update a session's status and the log for a connection progress event.
+    fn handle_connect_event(&mut self, id: SessionId, event: ConnectEvent) {
+        let Some(session) = self.sessions.get_mut(id) else {
+            return;
+        };
+        let name = session.server.name.clone();
+
+        let message = match event {
+            ConnectEvent::VpnConnecting { vpn, backend } => {
+                session.status = ConnectionStatus::ConnectingVpn;
+                format!("Connecting to VPN: {} (via {})", vpn, backend)
+            }
+            ConnectEvent::VpnAlreadyUp { vpn } => {
+                format!("VPN {} is already connected, leaving it up", vpn)
+            }
+            ConnectEvent::VpnWaiting => {
+                session.status = ConnectionStatus::WaitingForVpn;
+                "Waiting for VPN to establish...".to_string()
+            }
+            ConnectEvent::VpnUp => "VPN connected".to_string(),
+            ConnectEvent::VpnTimeout => "VPN connection timed out, trying anyway".to_string(),
+            ConnectEvent::Probing { host } => {
+                session.status = ConnectionStatus::CheckingConnectivity;
+                format!("Checking connectivity to {}...", host)
+            }
+            ConnectEvent::ProbeOk { host } => {
+                session.status = ConnectionStatus::StartingSession;
+                format!("{} is reachable, starting session...", host)
+            }
+            ConnectEvent::ProbeFailed { host } => format!("{} is not reachable", host),
+            ConnectEvent::TunnelOpen { local_port } => {
+                format!("SSH tunnel open on port {}", local_port)
+            }
+            ConnectEvent::SessionStarted { session } => {
+                format!("{} session started", session.name())
+            }
+            ConnectEvent::SessionSkipped {
+                session,
+                error,
+                hint,
+            } => format!("{} skipped: {}. {}", session.name(), error, hint),
+            ConnectEvent::SessionEnded => "Session ended".to_string(),
+        };
+        self.log_status(format!("{}: {}", name, message));
+    }
+
     /// Request quit with confirmation.
     pub fn request_quit(&mut self) {
-        if self.connected_server.is_some() {
+        if self.sessions.iter().any(|s| !s.is_finished()) {
             self.confirm_action = Some(ConfirmAction::Quit);
             self.confirm_selection = 0;
             self.go_to_screen(Screen::Confirm);
+        } else {
+            self.quit();
+        }
+    }
+
+    /// Quit, asking first whether to save unsaved changes.
+    fn quit(&mut self) {
+        if self.modified {
+            self.confirm_action = Some(ConfirmAction::SaveChanges);
+            self.confirm_selection = 1;
+            self.go_to_screen(Screen::Confirm);
         } else {
             self.should_quit = true;
         }
@@ -446,9 +1007,7 @@ impl App {
     pub fn add_server(&mut self) {
         self.edit_mode = false;
         self.edit_server_fields = EditServerFields::default();
-        self.edit_field_index = 0;
-        self.load_field_to_input();
-        self.go_to_screen(Screen::EditServer);
+        self.open_server_form();
     }
 
     /// Start editing selected server.
@@ -456,15 +1015,60 @@ impl App {
         if let Some(server) = self.current_server().cloned() {
             self.edit_mode = true;
             self.edit_server_fields = EditServerFields {
+                ssh: server.ssh_string().unwrap_or_default(),
                 name: server.name,
-                rdp: server.rdp,
-                ssh: server.ssh.unwrap_or_default(),
+                rdp: server.rdp.to_string(),
                 vpn: server.vpn,
+                group: server.group.unwrap_or_default(),
+                tags: server.tags.join(", "),
             };
-            self.edit_field_index = 0;
-            self.load_field_to_input();
-            self.go_to_screen(Screen::EditServer);
+            self.open_server_form();
+        }
+    }
+
+    /// Show the add/edit form for the fields already filled in.
+    fn open_server_form(&mut self) {
+        self.edit_field_index = 0;
+        self.edit_submitted = false;
+        self.known_vpns = platform::find_executable("nmcli").and_then(|_| {
+            vpn::nmcli_connections(false)
+                .map_err(|e| tracing::debug!("Failed to list VPN connections: {:#}", e))
+                .ok()
+        });
+        self.load_field_to_input();
+        self.go_to_screen(Screen::EditServer);
+    }
+
+    /// Validation errors of the add/edit form by field, taking the text being
+    /// typed into account.
+    pub fn edit_server_errors(&self) -> FieldErrors {
+        let mut fields = self.edit_server_fields.clone();
+        if let Some(field) = fields.get_mut(self.edit_field_index) {
+            field.clone_from(&self.input_buffer);
         }
+
+        let editing = self.edit_mode.then_some(self.selected_server);
+        let others: Vec<&Server> = self
+            .config
+            .servers
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| Some(i) != editing)
+            .map(|(_, server)| server)
+            .collect();
+
+        // Only NetworkManager connections can be checked for existence
+        let mut server = editing
+            .and_then(|i| self.config.servers.get(i))
+            .cloned()
+            .unwrap_or_default();
+        server.vpn = fields.vpn.trim().to_string();
+        let known_vpns = self
+            .known_vpns
+            .as_deref()
+            .filter(|_| vpn::backend_for(&server).name() == "nmcli");
+
+        server_form::validate(&fields, &others, known_vpns)
     }
 
     /// Delete selected server.
@@ -478,72 +1082,302 @@ impl App {
 
     /// Load current field to input buffer.
     fn load_field_to_input(&mut self) {
-        self.input_buffer = match self.edit_field_index {
-            0 => self.edit_server_fields.name.clone(),
-            1 => self.edit_server_fields.rdp.clone(),
-            2 => self.edit_server_fields.ssh.clone(),
-            3 => self.edit_server_fields.vpn.clone(),
-            _ => String::new(),
-        };
-        self.cursor_position = self.input_buffer.len();
+        self.input_buffer = self
+            .edit_server_fields
+            .get(self.edit_field_index)
+            .cloned()
+            .unwrap_or_default();
+        self.cursor_end();
     }
 
     /// Save current input to field.
     pub fn save_current_field(&mut self) {
-        match self.edit_field_index {
-            0 => self.edit_server_fields.name = self.input_buffer.clone(),
-            1 => self.edit_server_fields.rdp = self.input_buffer.clone(),
-            2 => self.edit_server_fields.ssh = self.input_buffer.clone(),
-            3 => self.edit_server_fields.vpn = self.input_buffer.clone(),
-            _ => {}
+        if let Some(field) = self.edit_server_fields.get_mut(self.edit_field_index) {
+            field.clone_from(&self.input_buffer);
         }
     }
 
-    /// Save the server being edited.
+    /// Save the server being edited; the form must have been validated.
     fn save_server(&mut self) {
         self.save_current_field();
+        let before = self.config.clone();
+        let selected = self.selected_server;
 
-        let server = Server {
-            name: self.edit_server_fields.name.clone(),
-            rdp: self.edit_server_fields.rdp.clone(),
-            ssh: if self.edit_server_fields.ssh.is_empty() {
-                None
-            } else {
-                Some(self.edit_server_fields.ssh.clone())
-            },
-            vpn: self.edit_server_fields.vpn.clone(),
+        // Start from the existing server so fields not shown in the form survive
+        let mut server = if self.edit_mode {
+            self.config.servers[self.selected_server].clone()
+        } else {
+            Server::default()
+        };
+        server.name = self.edit_server_fields.name.trim().to_string();
+        if let Ok(address) = self.edit_server_fields.rdp.parse::<RdpConfig>() {
+            server.rdp.host = address.host;
+            server.rdp.port = address.port;
+        }
+        server.vpn = self.edit_server_fields.vpn.trim().to_string();
+        let group = self.edit_server_fields.group.trim();
+        server.group = (!group.is_empty()).then(|| group.to_string());
+        server.tags = self
+            .edit_server_fields
+            .tags
+            .split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(str::to_string)
+            .collect();
+        server.ssh = match self.edit_server_fields.ssh.trim() {
+            "" => None,
+            target => target.parse::<SshConfig>().ok().map(|mut ssh| {
+                if let Some(previous) = &server.ssh {
+                    ssh.identity_file = previous.identity_file.clone();
+                    ssh.options = previous.options.clone();
+                }
+                ssh
+            }),
         };
 
+        let description = if self.edit_mode {
+            format!("Updated server '{}'", server.name)
+        } else {
+            format!("Added server '{}'", server.name)
+        };
         if self.edit_mode {
             self.config.servers[self.selected_server] = server;
-            self.log_status("Server updated");
+            self.refresh_status();
         } else {
             self.config.servers.push(server);
-            self.selected_server = self.config.servers.len() - 1;
-            self.log_status("Server added");
+            self.refresh_status();
+            self.select_server(self.config.servers.len() - 1);
+        }
+        self.config_changed(before, selected, description);
+    }
+
+    /// Move the selected server above (or below) the previous (or next)
+    /// server of its group.
+    pub fn move_selected_server(&mut self, up: bool) {
+        if !self.search_query.is_empty() {
+            self.log_status("Clear the search to reorder servers");
+            return;
+        }
+        let Some(server) = self.current_server() else {
+            return;
+        };
+        let name = server.name.clone();
+        let group = server.group_name().map(str::to_string);
+        let index = self.selected_server;
+
+        let same_group = |i: &usize| self.config.servers[*i].group_name() == group.as_deref();
+        let neighbor = if up {
+            (0..index).rev().find(same_group)
+        } else {
+            (index + 1..self.config.servers.len()).find(same_group)
+        };
+        let Some(neighbor) = neighbor else {
+            return;
+        };
+
+        let before = self.config.clone();
+        self.config.servers.swap(index, neighbor);
+        self.refresh_status();
+        self.select_server(neighbor);
+        let direction = if up { "up" } else { "down" };
+        self.config_changed(before, index, format!("Moved '{}' {}", name, direction));
+    }
+
+    /// Record a change to the configuration for undo and mark it modified.
+    ///
+    /// `before` and `selected` are the configuration and selected server
+    /// from before the change.
+    fn config_changed(&mut self, before: Config, selected: usize, description: String) {
+        if serialized(&before) == serialized(&self.config) {
+            return;
         }
+        self.history.record(description.clone(), before, selected);
+        self.log_status(description.clone());
+        self.last_action = Some(description);
+        self.config_updated();
+    }
+
+    /// Update the modified flag, saving right away with `auto_save`.
+    fn config_updated(&mut self) {
+        self.modified = serialized(&self.config) != self.saved_config;
+        if self.modified && self.config.settings.auto_save {
+            self.save_changes();
+        }
+    }
+
+    /// Take back the last configuration change.
+    pub fn undo(&mut self) {
+        match self.history.undo(self.config.clone(), self.selected_server) {
+            Some(entry) => self.restore(entry, "Undid"),
+            None => self.log_status("Nothing to undo"),
+        }
+    }
+
+    /// Make the last undone configuration change again.
+    pub fn redo(&mut self) {
+        match self.history.redo(self.config.clone(), self.selected_server) {
+            Some(entry) => self.restore(entry, "Redid"),
+            None => self.log_status("Nothing to redo"),
+        }
+    }
+
+    /// Switch to a configuration from the history.
+    fn restore(&mut self, entry: Entry, verb: &str) {
+        let previous = std::mem::replace(&mut self.config, entry.config);
+        self.restart_status(&previous.settings);
+        self.select_server(
+            entry
+                .selected
+                .min(self.config.servers.len().saturating_sub(1)),
+        );
+
+        let message = format!("{}: {}", verb, entry.description);
+        self.log_status(message.clone());
+        self.last_action = Some(message);
+        self.config_updated();
+    }
+
+    /// Restart reachability probing, respawning the worker if its settings changed.
+    fn restart_status(&mut self, previous: &Settings) {
+        let settings = &self.config.settings;
+        // The worker's interval and timeout are fixed when it starts
+        if settings.status_interval_secs != previous.status_interval_secs
+            || settings.tcp_timeout_ms != previous.tcp_timeout_ms
+        {
+            self.status_worker = spawn_status_worker(&self.config);
+            self.server_status.clear();
+        } else {
+            self.refresh_status();
+        }
+    }
+
+    /// Save the configuration and log the outcome; true if it was saved.
+    pub fn save_changes(&mut self) -> bool {
+        match self.save_config() {
+            Ok(existing) => {
+                self.modified = false;
+                self.saved_config = serialized(&self.config);
+                let path = self.config_path.display().to_string();
+                self.log_status(format!("Configuration saved to {}", path));
+                if let Some(issue) = existing.first() {
+                    self.log_status(format!(
+                        "The file already had {} problem(s), e.g. {}",
+                        existing.len(),
+                        issue
+                    ));
+                }
+                true
+            }
+            Err(e) => {
+                self.log_status(format!("Failed to save config: {:#}", e));
+                false
+            }
+        }
+    }
+
+    /// Field selected in the Settings screen.
+    pub fn selected_setting_field(&self) -> SettingField {
+        SettingField::ALL[self.selected_setting.min(SettingField::ALL.len() - 1)]
+    }
+
+    /// Start typing the selected setting, or switch a choice to its next value.
+    pub fn edit_setting(&mut self) {
+        let field = self.selected_setting_field();
+        if field.is_choice() {
+            self.cycle_setting(true);
+            return;
+        }
+        self.input_buffer = field.input(&self.config.settings);
+        self.cursor_end();
+        self.settings_editing = true;
+        self.settings_error = None;
+    }
+
+    /// Switch the selected choice setting to its next or previous value.
+    pub fn cycle_setting(&mut self, forward: bool) {
+        let field = self.selected_setting_field();
+        if !field.is_choice() {
+            return;
+        }
+        let previous = self.config.settings.clone();
+        field.cycle(&mut self.config.settings, forward);
+        self.settings_changed(&previous);
+    }
+
+    /// Validate and apply the typed setting; invalid input stays in the editor.
+    pub fn commit_setting(&mut self) {
+        let field = self.selected_setting_field();
+        let previous = self.config.settings.clone();
+        match field.apply(&mut self.config.settings, &self.input_buffer) {
+            Ok(()) => {
+                self.settings_editing = false;
+                self.settings_error = None;
+                if field.input(&previous) != field.input(&self.config.settings) {
+                    self.settings_changed(&previous);
+                }
+            }
+            Err(message) => self.settings_error = Some(format!("{} {}", field.label(), message)),
+        }
+    }
+
+    /// Stop typing a setting without changing it.
+    pub fn cancel_setting_edit(&mut self) {
+        self.settings_editing = false;
+        self.settings_error = None;
+    }
+
+    /// Apply changed settings to the running app and mark the config modified.
+    fn settings_changed(&mut self, previous: &Settings) {
+        self.restart_status(previous);
+        let mut before = self.config.clone();
+        before.settings = previous.clone();
+        let field = self.selected_setting_field();
+        let description = format!(
+            "{} set to {}",
+            field.label(),
+            field.display(&self.config.settings)
+        );
+        self.config_changed(before, self.selected_server, description);
+    }
+
+    /// Whether keystrokes are typed into `input_buffer`.
+    fn is_typing(&self) -> bool {
+        self.screen == Screen::EditServer
+            || (self.screen == Screen::Settings && self.settings_editing)
+    }
+
+    /// Byte offset in `input_buffer` of the character at `position`.
+    fn input_offset(&self, position: usize) -> usize {
+        self.input_buffer
+            .char_indices()
+            .nth(position)
+            .map_or(self.input_buffer.len(), |(offset, _)| offset)
     }
 
     /// Handle character input.
     pub fn handle_char(&mut self, c: char) {
-        if self.screen == Screen::EditServer {
-            self.input_buffer.insert(self.cursor_position, c);
+        if self.is_typing() {
+            let offset = self.input_offset(self.cursor_position);
+            self.input_buffer.insert(offset, c);
             self.cursor_position += 1;
         }
     }
 
     /// Handle backspace.
     pub fn handle_backspace(&mut self) {
-        if self.screen == Screen::EditServer && self.cursor_position > 0 {
+        if self.is_typing() && self.cursor_position > 0 {
             self.cursor_position -= 1;
-            self.input_buffer.remove(self.cursor_position);
+            let offset = self.input_offset(self.cursor_position);
+            self.input_buffer.remove(offset);
         }
     }
 
     /// Handle delete.
     pub fn handle_delete(&mut self) {
-        if self.screen == Screen::EditServer && self.cursor_position < self.input_buffer.len() {
-            self.input_buffer.remove(self.cursor_position);
+        if self.is_typing() && self.cursor_position < self.input_buffer.chars().count() {
+            let offset = self.input_offset(self.cursor_position);
+            self.input_buffer.remove(offset);
         }
     }
 
@@ -556,14 +1390,19 @@ impl App {
 
     /// Move cursor right.
     pub fn cursor_right(&mut self) {
-        if self.cursor_position < self.input_buffer.len() {
+        if self.cursor_position < self.input_buffer.chars().count() {
             self.cursor_position += 1;
         }
     }
 
-    /// Get connection duration.
+    /// Move cursor past the last character.
+    pub fn cursor_end(&mut self) {
+        self.cursor_position = self.input_buffer.chars().count();
+    }
+
+    /// Get the active session's duration.
     pub fn connection_duration(&self) -> Option<Duration> {
-        self.connection_start.map(|start| start.elapsed())
+        self.active().map(Session::duration)
     }
 
     /// Format duration as string.
@@ -580,20 +1419,285 @@ impl App {
         }
     }
 
-    /// Save configuration to file.
-    pub fn save_config(&self) -> anyhow::Result<()> {
-        let config_path = Config::default_path();
-        let content = toml::to_string_pretty(&self.config)?;
-        std::fs::write(&config_path, content)?;
-        Ok(())
+    /// Save configuration to the file it was loaded from.
+    ///
+    /// Only changed keys are rewritten, so comments and formatting survive.
+    /// Refuses changes that add problems `sap_it validate` would report, and
+    /// returns those the file already had.
+    pub fn save_config(&self) -> anyhow::Result<Vec<Issue>> {
+        let mut file = ConfigFile::open_or_new(&self.config_path)?;
+        file.update(&self.config)?;
+        file.save()
+    }
+}
+
+/// `config` as TOML, to tell whether it differs from another version.
+fn serialized(config: &Config) -> String {
+    toml::to_string(config).unwrap_or_default()
+}
+
+/// Start background reachability probes unless disabled in the settings.
+fn spawn_status_worker(config: &Config) -> Option<StatusWorker> {
+    match config.settings.status_interval_secs {
+        0 => None,
+        secs => StatusWorker::spawn(config, Duration::from_secs(secs))
+            .map_err(|e| tracing::warn!("Failed to start status worker: {}", e))
+            .ok(),
     }
 }
 
 impl Drop for App {
     fn drop(&mut self) {
-        // Ensure VPN is disconnected when app exits
-        if let Some(vpn) = self.connected_vpn.take() {
-            let _ = platform::disconnect_vpn(&vpn);
+        // Ensure tunnels and VPNs are torn down before the app exits
+        self.sessions.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ProbeMethod;
+
+    /// App on a saved copy of the default config, without background probes.
+    fn app(dir: &tempfile::TempDir) -> App {
+        let mut config = Config::default_config();
+        config.settings.status_interval_secs = 0;
+        let path = dir.path().join("servers.toml");
+        std::fs::write(&path, toml::to_string_pretty(&config).unwrap()).unwrap();
+        App::new(config, path)
+    }
+
+    /// Delete the selected server through the confirmation dialog.
+    fn delete_selected(app: &mut App) {
+        app.delete_selected_server();
+        app.confirm_selection = 1;
+        app.confirm_selection();
+    }
+
+    #[test]
+    fn test_quit_asks_to_save_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = app(&dir);
+        delete_selected(&mut app);
+        assert!(app.modified);
+
+        app.request_quit();
+        assert!(matches!(
+            app.confirm_action,
+            Some(ConfirmAction::SaveChanges)
+        ));
+        assert!(!app.should_quit);
+
+        app.confirm_selection = 1;
+        app.confirm_selection();
+        assert!(app.should_quit);
+        assert!(!app.modified);
+        let saved = Config::load(&app.config_path).unwrap();
+        assert_eq!(saved.servers.len(), app.config.servers.len());
+    }
+
+    #[test]
+    fn test_quit_without_saving() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = app(&dir);
+        let servers = app.config.servers.len();
+        delete_selected(&mut app);
+
+        app.request_quit();
+        app.confirm_selection = 0;
+        app.confirm_selection();
+        assert!(app.should_quit);
+        assert!(app.modified);
+        assert_eq!(
+            Config::load(&app.config_path).unwrap().servers.len(),
+            servers
+        );
+    }
+
+    #[test]
+    fn test_settings_form() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = app(&dir);
+        app.go_to_screen(Screen::Settings);
+
+        // Ping retries
+        app.select_next();
+        app.select_next();
+        app.edit_setting();
+        assert!(app.settings_editing);
+        app.handle_backspace();
+        app.handle_char('0');
+        app.commit_setting();
+        assert!(app.settings_editing);
+        assert_eq!(
+            app.settings_error.as_deref(),
+            Some("Ping Retries must be at least 1")
+        );
+        assert!(!app.modified);
+
+        app.handle_backspace();
+        app.handle_char('5');
+        app.commit_setting();
+        assert!(!app.settings_editing);
+        assert_eq!(app.config.settings.ping_retries, 5);
+
+        // Probe method
+        app.select_next();
+        app.cycle_setting(true);
+        assert_eq!(app.config.settings.probe_method, ProbeMethod::Tcp);
+        assert!(app.modified);
+
+        assert!(app.save_changes());
+        let saved = Config::load(&app.config_path).unwrap();
+        assert_eq!(saved.settings.ping_retries, 5);
+        assert_eq!(saved.settings.probe_method, ProbeMethod::Tcp);
+    }
+
+    /// Type `text` into the selected form field, replacing its content.
+    fn type_field(app: &mut App, text: &str) {
+        app.input_buffer.clear();
+        app.cursor_position = 0;
+        text.chars().for_each(|c| app.handle_char(c));
+    }
+
+    #[test]
+    fn test_multibyte_input() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = app(&dir);
+        app.add_server();
+
+        type_field(&mut app, "Müller");
+        app.handle_backspace();
+        app.cursor_left();
+        app.cursor_left();
+        app.handle_char('é');
+        assert_eq!(app.input_buffer, "Müléle");
+        app.handle_delete();
+        app.cursor_position = 1;
+        app.handle_delete();
+        assert_eq!(app.input_buffer, "Mlée");
+
+        app.cursor_right();
+        app.cursor_end();
+        app.handle_char('ß');
+        app.cursor_right();
+        assert_eq!(app.cursor_position, 5);
+        assert_eq!(app.input_buffer, "Mléeß");
+    }
+
+    #[test]
+    fn test_invalid_server_form_is_not_saved() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = app(&dir);
+        let servers = app.config.servers.len();
+        let existing = app.config.servers[1].name.to_lowercase();
+
+        app.add_server();
+        type_field(&mut app, &existing);
+        app.confirm_selection();
+        type_field(&mut app, "10.0.0.300");
+        app.confirm_selection();
+        type_field(&mut app, "root@");
+        for _ in 2..EDIT_FIELD_COUNT {
+            app.confirm_selection();
+        }
+
+        // Stays in the form on the first invalid field
+        assert_eq!(app.screen, Screen::EditServer);
+        assert!(app.edit_submitted);
+        assert_eq!(app.edit_field_index, 0);
+        let errors = app.edit_server_errors();
+        assert!(errors[..3].iter().all(Option::is_some));
+        assert_eq!(app.config.servers.len(), servers);
+
+        type_field(&mut app, "Nova");
+        app.save_current_field();
+        app.edit_field_index = 1;
+        type_field(&mut app, "10.0.0.30");
+        app.save_current_field();
+        app.edit_field_index = 2;
+        type_field(&mut app, "root@10.0.0.30");
+        app.save_current_field();
+        app.edit_field_index = EDIT_FIELD_COUNT - 1;
+        app.load_field_to_input();
+        app.confirm_selection();
+
+        assert_eq!(app.screen, Screen::ServerList);
+        assert_eq!(app.config.servers.len(), servers + 1);
+        assert_eq!(app.config.servers[servers].name, "Nova");
+    }
+
+    fn names(app: &App) -> Vec<String> {
+        app.config.servers.iter().map(|s| s.name.clone()).collect()
+    }
+
+    #[test]
+    fn test_undo_redo_delete() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = app(&dir);
+        let original = names(&app);
+        app.select_server(1);
+        delete_selected(&mut app);
+        assert_eq!(
+            app.last_action,
+            Some(format!("Deleted server '{}'", original[1]))
+        );
+
+        app.undo();
+        assert_eq!(names(&app), original);
+        assert_eq!(app.selected_server, 1);
+        assert!(!app.modified);
+        assert!(app.last_action.as_deref().unwrap().starts_with("Undid: "));
+
+        app.redo();
+        assert_eq!(names(&app).len(), original.len() - 1);
+        assert!(app.modified);
+        assert!(!app.history.can_redo());
+    }
+
+    #[test]
+    fn test_reorder_within_group() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = app(&dir);
+        app.config.servers.truncate(3);
+        for (server, group) in app.config.servers.iter_mut().zip(["A", "B", "A"]) {
+            server.group = Some(group.to_string());
         }
+        let original = names(&app);
+
+        // The first server's neighbor in group A is the third one
+        app.select_server(0);
+        app.move_selected_server(false);
+        let moved = [&original[2], &original[1], &original[0]];
+        assert_eq!(names(&app), moved.map(String::clone));
+        assert_eq!(app.selected_server, 2);
+        // Already last in its group
+        app.move_selected_server(false);
+
+        app.undo();
+        assert_eq!(names(&app), original);
+        assert_eq!(app.selected_server, 0);
+
+        assert!(app.save_changes());
+        app.redo();
+        assert!(app.save_changes());
+        let saved = Config::load(&app.config_path).unwrap();
+        assert_eq!(saved.servers[0].name, original[2]);
+    }
+
+    #[test]
+    fn test_auto_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = app(&dir);
+        app.config.settings.auto_save = true;
+        delete_selected(&mut app);
+
+        assert!(!app.modified);
+        let saved = Config::load(&app.config_path).unwrap();
+        assert!(saved.settings.auto_save);
+        assert_eq!(saved.servers.len(), app.config.servers.len());
+
+        app.request_quit();
+        assert!(app.should_quit);
     }
 }
@@ -0,0 +1,310 @@
+//! Background connection task for the TUI.
+//!
+//! VPN connect, probing and session launch run on a worker thread that
+//! reports progress over a channel, so the render loop never blocks.
+//! Cancelling returns control immediately; the worker tears the VPN and
+//! tunnel down on its own.
+
+use crate::config::{Server, Settings};
+use crate::connection::{ConnectError, ConnectEvent, ConnectionManager, ConnectionType, VpnUsage};
+use std::process::Child;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::debug;
+
+/// Message from the connection task to the UI.
+#[derive(Debug)]
+pub enum TaskMessage {
+    /// Connection progress.
+    Event(ConnectEvent),
+    /// Sessions are running, with the RDP client's PID if one was started.
+    Connected { rdp_pid: Option<u32> },
+    /// The connection failed.
+    Failed(ConnectError),
+    /// The task finished and the VPN/tunnel were torn down.
+    Finished,
+}
+
+/// Handle to a running connection task.
+pub struct ConnectTask {
+    messages: mpsc::UnboundedReceiver<TaskMessage>,
+    cancel: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+    finished: bool,
+}
+
+impl ConnectTask {
+    /// Start connecting to `server` in the background, sharing VPNs with
+    /// other tasks through `vpn_usage`.
+    pub fn spawn(
+        server: Server,
+        settings: Settings,
+        conn_type: ConnectionType,
+        vpn_usage: VpnUsage,
+    ) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let flag = cancel.clone();
+
+        let handle = std::thread::Builder::new()
+            .name("connect".to_string())
+            .spawn(move || run(server, settings, conn_type, vpn_usage, flag, tx))
+            .map_err(|e| debug!("Failed to spawn connection task: {}", e))
+            .ok();
+
+        Self {
+            messages: rx,
+            cancel,
+            handle,
+            finished: false,
+        }
+    }
+
+    /// Next pending message, if any.
+    pub fn try_recv(&mut self) -> Option<TaskMessage> {
+        match self.messages.try_recv() {
+            Ok(message) => {
+                self.finished |= matches!(message, TaskMessage::Finished);
+                Some(message)
+            }
+            Err(mpsc::error::TryRecvError::Empty) => None,
+            // The worker died (or never started) without saying goodbye
+            Err(mpsc::error::TryRecvError::Disconnected) if !self.finished => {
+                self.finished = true;
+                Some(TaskMessage::Finished)
+            }
+            Err(mpsc::error::TryRecvError::Disconnected) => None,
+        }
+    }
+
+    /// Ask the task to stop; it disconnects in the background.
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether the task has finished.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Cancel and wait until the VPN and tunnel are torn down.
+    pub fn shutdown(mut self) {
+        self.cancel();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Worker: connect, start the sessions and wait until they end or the task
+/// is cancelled. Dropping the manager tears the tunnel and VPN down.
+fn run(
+    server: Server,
+    settings: Settings,
+    conn_type: ConnectionType,
+    vpn_usage: VpnUsage,
+    cancel: Arc<AtomicBool>,
+    tx: mpsc::UnboundedSender<TaskMessage>,
+) {
+    let events = tx.clone();
+    let manager = ConnectionManager::new(server, settings, cancel)
+        .with_vpn_usage(vpn_usage)
+        .with_events(move |event| {
+            let _ = events.send(TaskMessage::Event(event));
+        });
+
+    let mut rdp = match connect(&manager, conn_type) {
+        Ok(child) => child,
+        Err(e) => {
+            if !manager.is_shutdown() {
+                let _ = tx.send(TaskMessage::Failed(e));
+            }
+            drop(manager);
+            let _ = tx.send(TaskMessage::Finished);
+            return;
+        }
+    };
+
+    if !manager.is_shutdown() {
+        let rdp_pid = rdp.as_ref().map(Child::id);
+        let _ = tx.send(TaskMessage::Connected { rdp_pid });
+    }
+
+    // Keep the VPN up until the RDP client exits or the user disconnects.
+    // Without RDP, the UI cancels the task once its SSH session ends.
+    while !manager.is_shutdown() {
+        match rdp.as_mut().map(|child| child.try_wait()) {
+            Some(Ok(Some(_))) | Some(Err(_)) => {
+                let _ = tx.send(TaskMessage::Event(ConnectEvent::SessionEnded));
+                break;
+            }
+            _ => std::thread::sleep(Duration::from_millis(200)),
+        }
+    }
+
+    if let Some(mut child) = rdp {
+        if manager.is_shutdown() {
+            let _ = child.kill();
+        }
+        let _ = child.wait();
+    }
+
+    drop(manager);
+    let _ = tx.send(TaskMessage::Finished);
+}
+
+/// Bring up the VPN, start the RDP client and check the SSH host as requested.
+///
+/// The SSH client itself is started by the UI, which owns the terminal.
+fn connect(
+    manager: &ConnectionManager,
+    conn_type: ConnectionType,
+) -> Result<Option<Child>, ConnectError> {
+    manager.connect_vpn()?;
+
+    let rdp = match conn_type {
+        ConnectionType::Rdp => Some(manager.start_rdp()?),
+        // Without RDP the SSH session still goes ahead
+        ConnectionType::Both => manager.start_rdp_or_skip()?,
+        ConnectionType::Ssh => None,
+    };
+
+    if conn_type != ConnectionType::Rdp {
+        manager.check_ssh_reachable()?;
+    }
+
+    Ok(rdp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ProbeMethod;
+    use std::net::TcpListener;
+    use std::time::Instant;
+
+    /// Collect messages until the task finishes.
+    fn drain(task: &mut ConnectTask) -> Vec<TaskMessage> {
+        let mut messages = Vec::new();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while Instant::now() < deadline {
+            match task.try_recv() {
+                Some(TaskMessage::Finished) => {
+                    messages.push(TaskMessage::Finished);
+                    break;
+                }
+                Some(message) => messages.push(message),
+                None => std::thread::sleep(Duration::from_millis(10)),
+            }
+        }
+        messages
+    }
+
+    #[test]
+    fn test_unreachable_host_fails() {
+        // Bind then drop to get a port nobody listens on
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let server = Server {
+            name: "Closed".to_string(),
+            rdp: format!("127.0.0.1:{}", port).parse().unwrap(),
+            probe_method: Some(ProbeMethod::Tcp),
+            ..Default::default()
+        };
+        let settings = Settings {
+            ping_retries: 1,
+            ..Default::default()
+        };
+
+        let mut task =
+            ConnectTask::spawn(server, settings, ConnectionType::Rdp, VpnUsage::default());
+        let messages = drain(&mut task);
+
+        assert!(matches!(
+            messages.first(),
+            Some(TaskMessage::Event(ConnectEvent::Probing { .. }))
+        ));
+        assert!(messages
+            .iter()
+            .any(|m| matches!(m, TaskMessage::Failed(ConnectError::Unreachable { .. }))));
+        assert!(matches!(messages.last(), Some(TaskMessage::Finished)));
+    }
+
+    #[test]
+    fn test_cancel_ends_ssh_only_task() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = Server {
+            name: "NoVpn".to_string(),
+            rdp: "127.0.0.1".parse().unwrap(),
+            ssh: Some(format!("root@127.0.0.1:{}", port).parse().unwrap()),
+            probe_method: Some(ProbeMethod::Tcp),
+            ..Default::default()
+        };
+
+        let mut task = ConnectTask::spawn(
+            server,
+            Settings::default(),
+            ConnectionType::Ssh,
+            VpnUsage::default(),
+        );
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !matches!(task.try_recv(), Some(TaskMessage::Connected { .. })) {
+            assert!(Instant::now() < deadline, "task never connected");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        task.cancel();
+        assert!(matches!(
+            drain(&mut task).last(),
+            Some(TaskMessage::Finished)
+        ));
+    }
+
+    #[test]
+    fn test_both_goes_on_without_rdp() {
+        let rdp_port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let ssh_port = listener.local_addr().unwrap().port();
+        let server = Server {
+            name: "NoRdp".to_string(),
+            rdp: format!("127.0.0.1:{}", rdp_port).parse().unwrap(),
+            ssh: Some(format!("root@127.0.0.1:{}", ssh_port).parse().unwrap()),
+            probe_method: Some(ProbeMethod::Tcp),
+            ..Default::default()
+        };
+        let settings = Settings {
+            ping_retries: 1,
+            ..Default::default()
+        };
+
+        let mut task =
+            ConnectTask::spawn(server, settings, ConnectionType::Both, VpnUsage::default());
+        let mut skipped = false;
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            match task.try_recv() {
+                Some(TaskMessage::Event(ConnectEvent::SessionSkipped { .. })) => skipped = true,
+                Some(TaskMessage::Connected { rdp_pid }) => {
+                    assert_eq!(rdp_pid, None);
+                    break;
+                }
+                Some(TaskMessage::Failed(e)) => panic!("connection failed: {}", e),
+                _ => std::thread::sleep(Duration::from_millis(10)),
+            }
+            assert!(Instant::now() < deadline, "task never connected");
+        }
+        assert!(skipped);
+        task.shutdown();
+    }
+}
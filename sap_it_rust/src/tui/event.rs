@@ -31,6 +31,11 @@ impl EventHandler {
         }
     }
 
+    /// Change the tick rate.
+    pub fn set_tick_rate(&mut self, tick_rate_ms: u64) {
+        self.tick_rate = Duration::from_millis(tick_rate_ms);
+    }
+
     /// Poll for the next event.
     pub fn next(&self) -> anyhow::Result<Event> {
         if event::poll(self.tick_rate)? {
@@ -50,13 +55,25 @@ impl EventHandler {
 pub fn handle_key_event(app: &mut super::app::App, key: KeyEvent) {
     use super::app::Screen;
 
+    // The focused embedded terminal gets every key except F12
+    if app.screen == Screen::Connected && app.pty_focused {
+        match (key.code, app.pty()) {
+            (KeyCode::F(12), _) => app.toggle_pty_focus(),
+            (_, Some(pty)) => pty.send_key(key),
+            (_, None) => {}
+        }
+        return;
+    }
+
     // Global shortcuts
     match key.code {
         KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
             app.request_quit();
             return;
         }
-        KeyCode::Char('q') if app.screen != Screen::EditServer => {
+        KeyCode::Char('q')
+            if app.screen != Screen::EditServer && !app.search_active && !app.settings_editing =>
+        {
             app.request_quit();
             return;
         }
@@ -73,38 +90,49 @@ pub fn handle_key_event(app: &mut super::app::App, key: KeyEvent) {
         Screen::Settings => handle_settings(app, key),
         Screen::EditServer => handle_edit_server(app, key),
         Screen::Confirm => handle_confirm(app, key),
+        Screen::Sessions => handle_sessions(app, key),
     }
 }
 
 fn handle_server_list(app: &mut super::app::App, key: KeyEvent) {
+    if app.search_active {
+        handle_search(app, key);
+        return;
+    }
+
     match key.code {
+        KeyCode::Char('/') => app.start_search(),
+        KeyCode::Esc if !app.search_query.is_empty() => app.clear_search(),
         KeyCode::Up | KeyCode::Char('k') => app.select_previous(),
         KeyCode::Down | KeyCode::Char('j') => app.select_next(),
         KeyCode::Enter | KeyCode::Char(' ') => app.confirm_selection(),
+        KeyCode::Left | KeyCode::Char('h') => app.collapse_group(),
+        KeyCode::Right | KeyCode::Char('l') => app.expand_group(),
         KeyCode::Char('a') => app.add_server(),
         KeyCode::Char('e') => app.edit_selected_server(),
         KeyCode::Char('d') | KeyCode::Delete => app.delete_selected_server(),
+        KeyCode::Char('K') => app.move_selected_server(true),
+        KeyCode::Char('J') => app.move_selected_server(false),
+        KeyCode::Char('u') => app.undo(),
+        KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => app.redo(),
         KeyCode::Char('?') | KeyCode::F(1) => app.go_to_screen(super::app::Screen::Help),
         KeyCode::Char('s') => app.go_to_screen(super::app::Screen::Settings),
-        KeyCode::Char('r') => {
+        KeyCode::Char('w') => app.go_to_screen(super::app::Screen::Sessions),
+        KeyCode::Char('r') if app.current_server().is_some() => {
             // Quick RDP connect
-            if app.current_server().is_some() {
-                app.selected_conn_type = 0;
-                app.confirm_selection();
-            }
+            app.selected_conn_type = 0;
+            app.confirm_selection();
         }
-        KeyCode::Char('S') => {
+        KeyCode::Char('S') if app.current_server().map(|s| s.has_ssh()).unwrap_or(false) => {
             // Quick SSH connect (if available)
-            if app.current_server().map(|s| s.has_ssh()).unwrap_or(false) {
-                app.selected_conn_type = 1;
-                app.confirm_selection();
-                app.confirm_selection();
-            }
+            app.selected_conn_type = 1;
+            app.confirm_selection();
+            app.confirm_selection();
         }
         KeyCode::Char('1'..='9') => {
             let index = key.code.to_string().parse::<usize>().unwrap_or(1) - 1;
             if index < app.config.servers.len() {
-                app.selected_server = index;
+                app.select_server(index);
                 app.confirm_selection();
             }
         }
@@ -112,6 +140,21 @@ fn handle_server_list(app: &mut super::app::App, key: KeyEvent) {
     }
 }
 
+fn handle_search(app: &mut super::app::App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Esc => app.clear_search(),
+        KeyCode::Enter => {
+            app.finish_search();
+            app.confirm_selection();
+        }
+        KeyCode::Up => app.select_previous(),
+        KeyCode::Down | KeyCode::Tab => app.select_next(),
+        KeyCode::Backspace => app.search_pop(),
+        KeyCode::Char(c) => app.search_push(c),
+        _ => {}
+    }
+}
+
 fn handle_connection_type(app: &mut super::app::App, key: KeyEvent) {
     match key.code {
         KeyCode::Up | KeyCode::Char('k') => app.select_previous(),
@@ -148,16 +191,35 @@ fn handle_connecting(app: &mut super::app::App, key: KeyEvent) {
 
 fn handle_connected(app: &mut super::app::App, key: KeyEvent) {
     match key.code {
-        KeyCode::Esc | KeyCode::Enter => app.confirm_selection(),
+        KeyCode::Esc => app.screen = super::app::Screen::ServerList,
+        KeyCode::Enter => app.confirm_selection(),
+        KeyCode::Char('s') => app.open_ssh(),
+        KeyCode::Char('w') => app.go_to_screen(super::app::Screen::Sessions),
+        KeyCode::F(12) => app.toggle_pty_focus(),
         KeyCode::Char('d') => {
-            app.confirm_action = Some(super::app::ConfirmAction::Disconnect);
-            app.confirm_selection = 0;
-            app.go_to_screen(super::app::Screen::Confirm);
+            if let Some(id) = app.active_session {
+                app.confirm_disconnect(id);
+            }
         }
         _ => {}
     }
 }
 
+fn handle_sessions(app: &mut super::app::App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') => app.select_previous(),
+        KeyCode::Down | KeyCode::Char('j') => app.select_next(),
+        KeyCode::Enter | KeyCode::Char(' ') => app.confirm_selection(),
+        KeyCode::Char('d') | KeyCode::Delete => {
+            if let Some(id) = app.selected_session_id() {
+                app.confirm_disconnect(id);
+            }
+        }
+        KeyCode::Esc | KeyCode::Char('w') => app.screen = super::app::Screen::ServerList,
+        _ => {}
+    }
+}
+
 fn handle_help(app: &mut super::app::App, key: KeyEvent) {
     match key.code {
         KeyCode::Esc | KeyCode::Enter | KeyCode::Char('?') | KeyCode::F(1) => app.go_back(),
@@ -178,17 +240,31 @@ fn handle_help(app: &mut super::app::App, key: KeyEvent) {
 }
 
 fn handle_settings(app: &mut super::app::App, key: KeyEvent) {
+    if app.settings_editing {
+        match key.code {
+            KeyCode::Esc => app.cancel_setting_edit(),
+            KeyCode::Enter => app.commit_setting(),
+            KeyCode::Backspace => app.handle_backspace(),
+            KeyCode::Delete => app.handle_delete(),
+            KeyCode::Left => app.cursor_left(),
+            KeyCode::Right => app.cursor_right(),
+            KeyCode::Char(c) => app.handle_char(c),
+            _ => {}
+        }
+        return;
+    }
+
     match key.code {
         KeyCode::Esc | KeyCode::Char('s') => app.go_back(),
         KeyCode::Up | KeyCode::Char('k') => app.select_previous(),
         KeyCode::Down | KeyCode::Char('j') => app.select_next(),
+        KeyCode::Enter | KeyCode::Char(' ') => app.edit_setting(),
+        KeyCode::Left | KeyCode::Char('h') => app.cycle_setting(false),
+        KeyCode::Right | KeyCode::Char('l') => app.cycle_setting(true),
+        KeyCode::Char('u') => app.undo(),
+        KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => app.redo(),
         KeyCode::Char('S') => {
-            // Save config
-            if let Err(e) = app.save_config() {
-                app.log_status(format!("Failed to save config: {}", e));
-            } else {
-                app.log_status("Configuration saved");
-            }
+            app.save_changes();
         }
         _ => {}
     }
@@ -226,7 +302,7 @@ fn handle_edit_server(app: &mut super::app::App, key: KeyEvent) {
             app.cursor_position = 0;
         }
         KeyCode::End => {
-            app.cursor_position = app.input_buffer.len();
+            app.cursor_end();
         }
         KeyCode::Char(c) => {
             app.handle_char(c);
@@ -244,7 +320,11 @@ fn handle_confirm(app: &mut super::app::App, key: KeyEvent) {
             app.confirm_selection = 1;
             app.confirm_selection();
         }
-        KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+        KeyCode::Char('n') | KeyCode::Char('N') => {
+            app.confirm_selection = 0;
+            app.confirm_selection();
+        }
+        KeyCode::Esc => {
             app.confirm_selection = 0;
             app.confirm_action = None;
             app.go_back();
@@ -0,0 +1,138 @@
+//! Undo/redo history of configuration changes made in the TUI.
+//!
+//! Each change stores a snapshot of the configuration from before it was
+//! made; configurations are small, so snapshots are simpler than inverse
+//! operations and cover every kind of change alike.
+
+use crate::config::Config;
+
+/// Changes kept for undo; older ones are dropped.
+const MAX_ENTRIES: usize = 100;
+
+/// A change and the state on the other side of it.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    /// What the change did, e.g. "Deleted server 'Ilmatex'".
+    pub description: String,
+    /// Configuration to go back (or forward) to.
+    pub config: Config,
+    /// Server selected in that configuration.
+    pub selected: usize,
+}
+
+/// Undo and redo stacks.
+#[derive(Debug, Default)]
+pub struct History {
+    undo: Vec<Entry>,
+    redo: Vec<Entry>,
+}
+
+impl History {
+    /// Record a change, given the configuration and selection from before it.
+    ///
+    /// A new change discards whatever could be redone.
+    pub fn record(&mut self, description: impl Into<String>, before: Config, selected: usize) {
+        self.undo.push(Entry {
+            description: description.into(),
+            config: before,
+            selected,
+        });
+        if self.undo.len() > MAX_ENTRIES {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+
+    /// Take the last change back, given the current state.
+    ///
+    /// Returns the entry to restore; the current state becomes redoable.
+    pub fn undo(&mut self, current: Config, selected: usize) -> Option<Entry> {
+        let entry = self.undo.pop()?;
+        self.redo.push(Entry {
+            description: entry.description.clone(),
+            config: current,
+            selected,
+        });
+        Some(entry)
+    }
+
+    /// Make the last undone change again, given the current state.
+    pub fn redo(&mut self, current: Config, selected: usize) -> Option<Entry> {
+        let entry = self.redo.pop()?;
+        self.undo.push(Entry {
+            description: entry.description.clone(),
+            config: current,
+            selected,
+        });
+        Some(entry)
+    }
+
+    /// Whether there is a change to undo.
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    /// Whether there is an undone change to redo.
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Server;
+
+    fn config(names: &[&str]) -> Config {
+        let mut config = Config::default_config();
+        config.servers = names
+            .iter()
+            .map(|name| Server {
+                name: name.to_string(),
+                ..Default::default()
+            })
+            .collect();
+        config
+    }
+
+    fn names(config: &Config) -> Vec<&str> {
+        config.servers.iter().map(|s| s.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut history = History::default();
+        assert!(history.undo(config(&[]), 0).is_none());
+
+        history.record("Added server 'B'", config(&["A"]), 0);
+        history.record("Deleted server 'A'", config(&["A", "B"]), 0);
+
+        let entry = history.undo(config(&["B"]), 0).unwrap();
+        assert_eq!(entry.description, "Deleted server 'A'");
+        assert_eq!(names(&entry.config), ["A", "B"]);
+        assert!(history.can_redo());
+
+        let entry = history.redo(entry.config, 0).unwrap();
+        assert_eq!(names(&entry.config), ["B"]);
+        assert!(!history.can_redo());
+
+        // A new change drops the redo stack
+        history.undo(entry.config, 0).unwrap();
+        history.record("Moved 'B' up", config(&["A", "B"]), 1);
+        assert!(!history.can_redo());
+        assert!(history.can_undo());
+    }
+
+    #[test]
+    fn test_history_is_bounded() {
+        let mut history = History::default();
+        for i in 0..MAX_ENTRIES + 10 {
+            history.record(format!("Change {}", i), config(&[]), 0);
+        }
+        let mut count = 0;
+        while history.undo(config(&[]), 0).is_some() {
+            count += 1;
+        }
+        assert_eq!(count, MAX_ENTRIES);
+    }
+}
@@ -3,7 +3,15 @@
 //! Provides an interactive TUI for managing server connections.
 
 pub mod app;
+pub mod connect;
 pub mod event;
+pub mod history;
+pub mod pty;
+pub mod search;
+pub mod server_form;
+pub mod session;
+pub mod settings;
+pub mod status;
 pub mod ui;
 
 pub use app::App;
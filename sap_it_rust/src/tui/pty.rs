@@ -0,0 +1,297 @@
+//! SSH running inside a pseudo-terminal, rendered as a TUI pane.
+//!
+//! A reader thread feeds the PTY output into a vt100 parser; the UI renders
+//! the parsed screen and forwards keystrokes to the PTY.
+
+use anyhow::{Context, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use portable_pty::{native_pty_system, Child, CommandBuilder, ExitStatus, MasterPty, PtySize};
+use std::io::{Read, Write};
+use std::sync::{Arc, Mutex};
+use tracing::debug;
+
+/// Lines of scrollback kept by the terminal emulator.
+const SCROLLBACK: usize = 1000;
+
+/// An ssh process attached to a PTY.
+pub struct PtySession {
+    master: Box<dyn MasterPty + Send>,
+    writer: Mutex<Box<dyn Write + Send>>,
+    child: Mutex<Box<dyn Child + Send + Sync>>,
+    /// How ssh ended, once it has.
+    exit: Mutex<Option<ExitStatus>>,
+    parser: Arc<Mutex<vt100::Parser>>,
+    size: Mutex<(u16, u16)>,
+}
+
+impl PtySession {
+    /// Spawn `ssh <args>` in a new PTY of the given size.
+    pub fn spawn(args: &[String], rows: u16, cols: u16) -> Result<Self> {
+        let size = PtySize {
+            rows,
+            cols,
+            pixel_width: 0,
+            pixel_height: 0,
+        };
+        let pair = native_pty_system()
+            .openpty(size)
+            .context("Failed to open a pseudo-terminal")?;
+
+        let mut command = CommandBuilder::new("ssh");
+        command.args(args);
+        command.env("TERM", "xterm-256color");
+        debug!("Executing in PTY: ssh {}", args.join(" "));
+
+        let child = pair
+            .slave
+            .spawn_command(command)
+            .context("Failed to execute ssh")?;
+        // Only the child keeps the slave end open, so reads end when it exits
+        drop(pair.slave);
+
+        let mut reader = pair
+            .master
+            .try_clone_reader()
+            .context("Failed to read from the pseudo-terminal")?;
+        let writer = pair
+            .master
+            .take_writer()
+            .context("Failed to write to the pseudo-terminal")?;
+
+        let parser = Arc::new(Mutex::new(vt100::Parser::new(rows, cols, SCROLLBACK)));
+        let output = parser.clone();
+        std::thread::Builder::new()
+            .name("pty-reader".to_string())
+            .spawn(move || {
+                let mut buf = [0u8; 8192];
+                loop {
+                    match reader.read(&mut buf) {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => {
+                            if let Ok(mut parser) = output.lock() {
+                                parser.process(&buf[..n]);
+                            }
+                        }
+                    }
+                }
+            })
+            .context("Failed to start the PTY reader")?;
+
+        Ok(Self {
+            master: pair.master,
+            writer: Mutex::new(writer),
+            child: Mutex::new(child),
+            exit: Mutex::new(None),
+            parser,
+            size: Mutex::new((rows, cols)),
+        })
+    }
+
+    /// The exit status of ssh, once it has ended.
+    ///
+    /// A child that can't be queried any more counts as ended with status 1.
+    pub fn exit_status(&self) -> Option<ExitStatus> {
+        let mut exit = self.exit.lock().ok()?;
+        if exit.is_none() {
+            *exit = match self.child.lock().map(|mut child| child.try_wait()) {
+                Ok(Ok(status)) => status,
+                _ => Some(ExitStatus::with_exit_code(1)),
+            };
+        }
+        exit.clone()
+    }
+
+    /// Text on the terminal screen, e.g. ssh's last error message.
+    pub fn screen_text(&self) -> String {
+        self.with_screen(|screen| screen.contents())
+            .unwrap_or_default()
+    }
+
+    /// Resize the PTY and emulator if the pane size changed.
+    pub fn resize(&self, rows: u16, cols: u16) {
+        let Ok(mut size) = self.size.lock() else {
+            return;
+        };
+        if *size == (rows, cols) || rows == 0 || cols == 0 {
+            return;
+        }
+        *size = (rows, cols);
+
+        if let Ok(mut parser) = self.parser.lock() {
+            parser.set_size(rows, cols);
+        }
+        let _ = self.master.resize(PtySize {
+            rows,
+            cols,
+            pixel_width: 0,
+            pixel_height: 0,
+        });
+    }
+
+    /// Run `f` with the current terminal screen.
+    pub fn with_screen<T>(&self, f: impl FnOnce(&vt100::Screen) -> T) -> Option<T> {
+        self.parser.lock().ok().map(|parser| f(parser.screen()))
+    }
+
+    /// Forward a key press to ssh.
+    pub fn send_key(&self, key: KeyEvent) {
+        let application_cursor = self
+            .with_screen(|screen| screen.application_cursor())
+            .unwrap_or(false);
+        if let Some(bytes) = key_bytes(key, application_cursor) {
+            if let Ok(mut writer) = self.writer.lock() {
+                let _ = writer.write_all(&bytes);
+                let _ = writer.flush();
+            }
+        }
+    }
+}
+
+impl Drop for PtySession {
+    fn drop(&mut self) {
+        if let Ok(mut child) = self.child.lock() {
+            if matches!(child.try_wait(), Ok(None)) {
+                let _ = child.kill();
+                let _ = child.wait();
+            }
+        }
+    }
+}
+
+/// Encode a key press as the bytes a terminal would send.
+fn key_bytes(key: KeyEvent, application_cursor: bool) -> Option<Vec<u8>> {
+    let cursor = |c: u8| {
+        if application_cursor {
+            vec![0x1b, b'O', c]
+        } else {
+            vec![0x1b, b'[', c]
+        }
+    };
+
+    let mut bytes = match key.code {
+        KeyCode::Char(c) if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            match c.to_ascii_lowercase() {
+                c @ 'a'..='z' => vec![c as u8 - b'a' + 1],
+                '@' | ' ' => vec![0],
+                '[' => vec![0x1b],
+                '\\' => vec![0x1c],
+                ']' => vec![0x1d],
+                _ => return None,
+            }
+        }
+        KeyCode::Char(c) => c.to_string().into_bytes(),
+        KeyCode::Enter => vec![b'\r'],
+        KeyCode::Backspace => vec![0x7f],
+        KeyCode::Tab => vec![b'\t'],
+        KeyCode::BackTab => b"\x1b[Z".to_vec(),
+        KeyCode::Esc => vec![0x1b],
+        KeyCode::Up => cursor(b'A'),
+        KeyCode::Down => cursor(b'B'),
+        KeyCode::Right => cursor(b'C'),
+        KeyCode::Left => cursor(b'D'),
+        KeyCode::Home => cursor(b'H'),
+        KeyCode::End => cursor(b'F'),
+        KeyCode::Insert => b"\x1b[2~".to_vec(),
+        KeyCode::Delete => b"\x1b[3~".to_vec(),
+        KeyCode::PageUp => b"\x1b[5~".to_vec(),
+        KeyCode::PageDown => b"\x1b[6~".to_vec(),
+        KeyCode::F(n @ 1..=4) => vec![0x1b, b'O', b'P' + n - 1],
+        KeyCode::F(n @ 5..=12) => {
+            let code = [15, 17, 18, 19, 20, 21, 23, 24][(n - 5) as usize];
+            format!("\x1b[{}~", code).into_bytes()
+        }
+        _ => return None,
+    };
+
+    // Alt sends an ESC prefix
+    if key.modifiers.contains(KeyModifiers::ALT) {
+        bytes.insert(0, 0x1b);
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn test_key_bytes() {
+        let none = KeyModifiers::NONE;
+        assert_eq!(
+            key_bytes(key(KeyCode::Char('a'), none), false),
+            Some(b"a".to_vec())
+        );
+        assert_eq!(
+            key_bytes(key(KeyCode::Char('c'), KeyModifiers::CONTROL), false),
+            Some(vec![3])
+        );
+        assert_eq!(
+            key_bytes(key(KeyCode::Char('x'), KeyModifiers::ALT), false),
+            Some(b"\x1bx".to_vec())
+        );
+        assert_eq!(
+            key_bytes(key(KeyCode::Enter, none), false),
+            Some(b"\r".to_vec())
+        );
+        assert_eq!(
+            key_bytes(key(KeyCode::Up, none), false),
+            Some(b"\x1b[A".to_vec())
+        );
+        assert_eq!(
+            key_bytes(key(KeyCode::Up, none), true),
+            Some(b"\x1bOA".to_vec())
+        );
+        assert_eq!(
+            key_bytes(key(KeyCode::F(5), none), false),
+            Some(b"\x1b[15~".to_vec())
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_exit_status_of_failed_ssh() {
+        if std::process::Command::new("ssh")
+            .arg("-V")
+            .output()
+            .is_err()
+        {
+            return; // ssh not installed
+        }
+        // Bind then drop to get a port nobody listens on
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let args: Vec<String> = ["-o", "BatchMode=yes", "-p", &port.to_string(), "127.0.0.1"]
+            .map(String::from)
+            .to_vec();
+        let pty = PtySession::spawn(&args, 24, 80).unwrap();
+
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        let status = loop {
+            if let Some(status) = pty.exit_status() {
+                break status;
+            }
+            assert!(std::time::Instant::now() < deadline, "ssh never exited");
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        };
+        // Let the reader thread catch up with the last output
+        std::thread::sleep(std::time::Duration::from_millis(200));
+
+        assert_eq!(status.exit_code(), 255);
+        let text = pty.screen_text();
+        assert!(text.contains("Connection refused"), "{}", text);
+
+        // The same error as from the suspended-terminal session
+        let target: crate::config::SshConfig = "127.0.0.1".parse().unwrap();
+        assert!(matches!(
+            crate::connection::check_ssh_exit(&target, Some(255), &text),
+            Err(crate::connection::ConnectError::Unreachable { .. })
+        ));
+    }
+}
@@ -0,0 +1,105 @@
+//! Fuzzy search over the server list.
+
+use crate::config::Server;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+
+/// A server matching the search query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerMatch {
+    /// Index into `config.servers`.
+    pub index: usize,
+    /// Best score over all searched fields.
+    pub score: i64,
+}
+
+/// Match servers against a query on name, host, VPN and tags, best first.
+///
+/// Servers with equal scores keep their config order.
+pub fn filter_servers(servers: &[Server], query: &str) -> Vec<ServerMatch> {
+    let matcher = SkimMatcherV2::default();
+
+    let mut matches: Vec<ServerMatch> = servers
+        .iter()
+        .enumerate()
+        .filter_map(|(index, server)| {
+            searchable_fields(server)
+                .filter_map(|field| matcher.fuzzy_match(field, query))
+                .max()
+                .map(|score| ServerMatch { index, score })
+        })
+        .collect();
+
+    matches.sort_by(|a, b| b.score.cmp(&a.score).then(a.index.cmp(&b.index)));
+    matches
+}
+
+/// Character positions in `text` matched by the query, for highlighting.
+pub fn match_indices(text: &str, query: &str) -> Vec<usize> {
+    SkimMatcherV2::default()
+        .fuzzy_indices(text, query)
+        .map(|(_, indices)| indices)
+        .unwrap_or_default()
+}
+
+/// Fields of a server that the search looks at.
+fn searchable_fields(server: &Server) -> impl Iterator<Item = &str> {
+    [
+        server.name.as_str(),
+        server.rdp.host.as_str(),
+        server.vpn.as_str(),
+    ]
+    .into_iter()
+    .chain(server.ssh_config().map(|ssh| ssh.host.as_str()))
+    .chain(server.tags.iter().map(String::as_str))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(name: &str, rdp: &str, vpn: &str, tags: &[&str]) -> Server {
+        Server {
+            name: name.to_string(),
+            rdp: rdp.parse().unwrap(),
+            vpn: vpn.to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_filter_servers() {
+        let servers = vec![
+            server("Ilmatex", "192.168.0.99", "ILMATEX", &[]),
+            server("Frodexim", "192.168.50.20", "FRODEXIM", &["sap"]),
+            server("BG Nova", "10.1.2.3", "Industrial Technik", &[]),
+        ];
+
+        let by_name: Vec<usize> = filter_servers(&servers, "frdx")
+            .iter()
+            .map(|m| m.index)
+            .collect();
+        assert_eq!(by_name, vec![1]);
+
+        let by_vpn: Vec<usize> = filter_servers(&servers, "technik")
+            .iter()
+            .map(|m| m.index)
+            .collect();
+        assert_eq!(by_vpn, vec![2]);
+
+        let by_tag: Vec<usize> = filter_servers(&servers, "sap")
+            .iter()
+            .map(|m| m.index)
+            .collect();
+        assert_eq!(by_tag, vec![1]);
+
+        assert!(filter_servers(&servers, "zzz").is_empty());
+    }
+
+    #[test]
+    fn test_match_indices() {
+        assert_eq!(match_indices("Frodexim", "fdx"), vec![0, 3, 5]);
+        assert!(match_indices("Frodexim", "zzz").is_empty());
+    }
+}
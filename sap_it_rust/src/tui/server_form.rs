@@ -0,0 +1,143 @@
+//! Validation of the add/edit server form.
+//!
+//! Each field is checked on its own so the form can show the problem next to
+//! the field; the server is only saved once every field is valid.
+
+use crate::config::{RdpConfig, Server, SshConfig};
+use crate::tui::app::{EditServerFields, EDIT_FIELD_COUNT};
+use crate::validate::is_valid_host;
+
+/// Problems with the form, by field index (None for valid fields).
+pub type FieldErrors = [Option<String>; EDIT_FIELD_COUNT];
+
+/// Check every field of the form.
+///
+/// `others` are the servers the name must not clash with; `known_vpns` are
+/// the NetworkManager connections, if the server's VPN is one and they
+/// could be listed.
+pub fn validate(
+    fields: &EditServerFields,
+    others: &[&Server],
+    known_vpns: Option<&[String]>,
+) -> FieldErrors {
+    [
+        validate_name(&fields.name, others).err(),
+        validate_rdp(&fields.rdp).err(),
+        validate_ssh(&fields.ssh).err(),
+        validate_vpn(&fields.vpn, known_vpns).err(),
+        None,
+        None,
+    ]
+}
+
+/// A name is required and must differ from the others, ignoring case.
+pub fn validate_name(name: &str, others: &[&Server]) -> Result<(), String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Name is required".to_string());
+    }
+    match others.iter().find(|s| s.name.eq_ignore_ascii_case(name)) {
+        Some(other) => Err(format!(
+            "'{}' is already used by another server",
+            other.name
+        )),
+        None => Ok(()),
+    }
+}
+
+/// The RDP address is required: a hostname or IP address with an optional port.
+pub fn validate_rdp(address: &str) -> Result<(), String> {
+    if address.trim().is_empty() {
+        return Err("RDP address is required".to_string());
+    }
+    let rdp: RdpConfig = address.parse()?;
+    check_host(&rdp.host)
+}
+
+/// The SSH target is optional; when given it must be `[user@]host[:port]`.
+pub fn validate_ssh(target: &str) -> Result<(), String> {
+    if target.trim().is_empty() {
+        return Ok(());
+    }
+    let ssh: SshConfig = target.parse()?;
+    if ssh.user.as_deref().is_some_and(|u| u.contains('@')) {
+        return Err(format!("Only one '@' allowed in '{}'", target.trim()));
+    }
+    check_host(&ssh.host)
+}
+
+/// The VPN is optional; when the known connections could be listed it must
+/// be one of them.
+pub fn validate_vpn(vpn: &str, known_vpns: Option<&[String]>) -> Result<(), String> {
+    let vpn = vpn.trim();
+    match known_vpns {
+        Some(known) if !vpn.is_empty() && !known.iter().any(|k| k == vpn) => {
+            Err(format!("No NetworkManager connection named '{}'", vpn))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Reject hosts that are neither an IP address nor a valid hostname.
+fn check_host(host: &str) -> Result<(), String> {
+    if is_valid_host(host) {
+        Ok(())
+    } else {
+        Err(format!("'{}' is not a valid hostname or IP address", host))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_validators() {
+        let other = Server {
+            name: "Ilmatex".to_string(),
+            ..Default::default()
+        };
+        assert!(validate_name("Nova", &[&other]).is_ok());
+        assert!(validate_name("  ", &[&other]).is_err());
+        assert!(validate_name("ILMATEX", &[&other]).is_err());
+
+        assert!(validate_rdp("192.168.0.99:3390").is_ok());
+        assert!(validate_rdp("[fe80::1]:3389").is_ok());
+        assert!(validate_rdp("").is_err());
+        assert!(validate_rdp("192.168.0.300").is_err());
+        assert!(validate_rdp("host:port").is_err());
+
+        assert!(validate_ssh("").is_ok());
+        assert!(validate_ssh("root@10.0.0.1:2222").is_ok());
+        assert!(validate_ssh("10.0.0.1").is_ok());
+        assert!(validate_ssh("@10.0.0.1").is_err());
+        assert!(validate_ssh("root@").is_err());
+        assert!(validate_ssh("a@b@10.0.0.1").is_err());
+        assert!(validate_ssh("root@bad host").is_err());
+
+        let known = ["ILMATEX".to_string()];
+        assert!(validate_vpn("", Some(&known)).is_ok());
+        assert!(validate_vpn("ILMATEX", Some(&known)).is_ok());
+        assert!(validate_vpn("FRODEXIM", Some(&known)).is_err());
+        assert!(validate_vpn("FRODEXIM", None).is_ok());
+    }
+
+    #[test]
+    fn test_validate_form() {
+        let fields = EditServerFields {
+            name: "Nova".to_string(),
+            rdp: "10.0.0.1".to_string(),
+            ..Default::default()
+        };
+        assert!(validate(&fields, &[], None).iter().all(Option::is_none));
+
+        let fields = EditServerFields {
+            rdp: "10.0.0.1:x".to_string(),
+            ..Default::default()
+        };
+        let errors = validate(&fields, &[], None);
+        assert!(errors[0].is_some());
+        assert!(errors[1].is_some());
+        assert!(errors[2..].iter().all(Option::is_none));
+    }
+}
@@ -0,0 +1,213 @@
+//! Registry of concurrent connection sessions.
+//!
+//! Every session owns its background connection task (VPN, tunnel, RDP
+//! client) and optionally an embedded SSH terminal, so several servers can
+//! be open at once and disconnected individually.
+
+use crate::config::{Server, Settings};
+use crate::connection::{ConnectionType, VpnUsage};
+use crate::tui::app::ConnectionStatus;
+use crate::tui::connect::{ConnectTask, TaskMessage};
+use crate::tui::pty::PtySession;
+use std::time::{Duration, Instant};
+
+/// Identifier of a session, unique for the lifetime of the registry.
+pub type SessionId = u64;
+
+/// One active (or failed) connection.
+pub struct Session {
+    /// Session identifier.
+    pub id: SessionId,
+    /// Server the session connects to.
+    pub server: Server,
+    /// VPN brought up for the session (None for servers without a VPN).
+    pub vpn: Option<String>,
+    /// Requested connection type.
+    pub conn_type: ConnectionType,
+    /// Current connection status.
+    pub status: ConnectionStatus,
+    /// PID of the RDP client, once started.
+    pub rdp_pid: Option<u32>,
+    /// Embedded SSH terminal, if open.
+    pub ssh: Option<PtySession>,
+    /// When the session was started.
+    pub started: Instant,
+    /// Background connection task.
+    task: ConnectTask,
+}
+
+impl Session {
+    /// Time since the session was started.
+    pub fn duration(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Whether the connection task has ended (only failed sessions linger).
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+
+    /// Messages received from the connection task since the last call.
+    pub fn drain_messages(&mut self) -> Vec<TaskMessage> {
+        std::iter::from_fn(|| self.task.try_recv()).collect()
+    }
+
+    /// Ask the connection task to stop and close the SSH terminal.
+    ///
+    /// The task tears down in the background and then reports `Finished`.
+    pub fn cancel(&mut self) {
+        self.ssh = None;
+        self.status = ConnectionStatus::Disconnecting;
+        self.task.cancel();
+    }
+}
+
+/// All sessions of the TUI, in start order.
+#[derive(Default)]
+pub struct SessionRegistry {
+    sessions: Vec<Session>,
+    next_id: SessionId,
+    vpn_usage: VpnUsage,
+}
+
+impl SessionRegistry {
+    /// Start connecting to `server` in a new session.
+    pub fn start(
+        &mut self,
+        server: Server,
+        settings: Settings,
+        conn_type: ConnectionType,
+    ) -> SessionId {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let status = if server.has_vpn() {
+            ConnectionStatus::ConnectingVpn
+        } else {
+            ConnectionStatus::CheckingConnectivity
+        };
+        let vpn = server.has_vpn().then(|| server.vpn.clone());
+        let task = ConnectTask::spawn(server.clone(), settings, conn_type, self.vpn_usage.clone());
+
+        self.sessions.push(Session {
+            id,
+            server,
+            vpn,
+            conn_type,
+            status,
+            rdp_pid: None,
+            ssh: None,
+            started: Instant::now(),
+            task,
+        });
+        id
+    }
+
+    /// Session by id.
+    pub fn get(&self, id: SessionId) -> Option<&Session> {
+        self.sessions.iter().find(|s| s.id == id)
+    }
+
+    /// Mutable session by id.
+    pub fn get_mut(&mut self, id: SessionId) -> Option<&mut Session> {
+        self.sessions.iter_mut().find(|s| s.id == id)
+    }
+
+    /// Session by position in start order.
+    pub fn get_index(&self, index: usize) -> Option<&Session> {
+        self.sessions.get(index)
+    }
+
+    /// Session connected to the server with the given name.
+    pub fn find_server(&self, name: &str) -> Option<SessionId> {
+        self.sessions
+            .iter()
+            .find(|s| s.server.name == name)
+            .map(|s| s.id)
+    }
+
+    /// Number of sessions currently holding `vpn` up.
+    pub fn vpn_users(&self, vpn: &str) -> usize {
+        self.vpn_usage.count(vpn)
+    }
+
+    /// Remove a session from the registry.
+    ///
+    /// A task that is still running keeps tearing down in the background.
+    pub fn remove(&mut self, id: SessionId) -> Option<Session> {
+        let index = self.sessions.iter().position(|s| s.id == id)?;
+        Some(self.sessions.remove(index))
+    }
+
+    /// Ids of all sessions, in start order.
+    pub fn ids(&self) -> Vec<SessionId> {
+        self.sessions.iter().map(|s| s.id).collect()
+    }
+
+    /// Iterate over all sessions, in start order.
+    pub fn iter(&self) -> impl Iterator<Item = &Session> {
+        self.sessions.iter()
+    }
+
+    /// Number of sessions.
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    /// Whether there are no sessions.
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
+    /// Cancel every session and wait until all of them are torn down.
+    pub fn shutdown(&mut self) {
+        for session in &self.sessions {
+            session.task.cancel();
+        }
+        for session in self.sessions.drain(..) {
+            drop(session.ssh);
+            session.task.shutdown();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ProbeMethod;
+    use std::net::TcpListener;
+
+    fn server(name: &str, port: u16) -> Server {
+        Server {
+            name: name.to_string(),
+            rdp: format!("127.0.0.1:{}", port).parse().unwrap(),
+            probe_method: Some(ProbeMethod::Tcp),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_registry_tracks_sessions() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let settings = Settings::default();
+
+        let mut registry = SessionRegistry::default();
+        let first = registry.start(server("One", port), settings.clone(), ConnectionType::Ssh);
+        let second = registry.start(server("Two", port), settings, ConnectionType::Ssh);
+
+        assert_ne!(first, second);
+        assert_eq!(registry.len(), 2);
+        assert_eq!(registry.find_server("Two"), Some(second));
+        assert_eq!(registry.find_server("Three"), None);
+        assert_eq!(registry.get(first).unwrap().vpn, None);
+        assert_eq!(registry.ids(), vec![first, second]);
+
+        let removed = registry.remove(first).unwrap();
+        assert_eq!(removed.server.name, "One");
+        assert_eq!(registry.ids(), vec![second]);
+
+        registry.shutdown();
+        assert!(registry.is_empty());
+    }
+}
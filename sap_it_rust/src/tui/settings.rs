@@ -0,0 +1,233 @@
+//! Editable fields of the Settings screen.
+//!
+//! Numbers are typed in and validated when committed; choices (probe method,
+//! SSH mode, RDP client, auto save) are cycled in place.
+
+use crate::config::{ProbeMethod, RdpClient, Settings, SshMode};
+
+/// A field of the Settings form, in display order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingField {
+    VpnTimeout,
+    PingTimeout,
+    PingRetries,
+    ProbeMethod,
+    ProbePort,
+    TcpTimeout,
+    StatusInterval,
+    SshMode,
+    RdpClient,
+    AutoSave,
+}
+
+impl SettingField {
+    /// All fields, in display order.
+    pub const ALL: [SettingField; 10] = [
+        SettingField::VpnTimeout,
+        SettingField::PingTimeout,
+        SettingField::PingRetries,
+        SettingField::ProbeMethod,
+        SettingField::ProbePort,
+        SettingField::TcpTimeout,
+        SettingField::StatusInterval,
+        SettingField::SshMode,
+        SettingField::RdpClient,
+        SettingField::AutoSave,
+    ];
+
+    /// Label shown in the form.
+    pub fn label(&self) -> &'static str {
+        match self {
+            SettingField::VpnTimeout => "VPN Timeout",
+            SettingField::PingTimeout => "Ping Timeout",
+            SettingField::PingRetries => "Ping Retries",
+            SettingField::ProbeMethod => "Probe Method",
+            SettingField::ProbePort => "Probe Port",
+            SettingField::TcpTimeout => "TCP Timeout",
+            SettingField::StatusInterval => "Status Refresh",
+            SettingField::SshMode => "SSH Mode",
+            SettingField::RdpClient => "RDP Client",
+            SettingField::AutoSave => "Auto Save",
+        }
+    }
+
+    /// Short explanation shown for the selected field.
+    pub fn hint(&self) -> &'static str {
+        match self {
+            SettingField::VpnTimeout => "Seconds to wait for a VPN to come up",
+            SettingField::PingTimeout => "Milliseconds to wait for each ping",
+            SettingField::PingRetries => "Pings before a host counts as unreachable",
+            SettingField::ProbeMethod => "How reachability is checked",
+            SettingField::ProbePort => "Port of the TCP probe unless a server sets its own",
+            SettingField::TcpTimeout => "Milliseconds to wait for a TCP probe",
+            SettingField::StatusInterval => "Seconds between list status checks, 0 disables",
+            SettingField::SshMode => "External hands over the terminal, embedded uses a pane",
+            SettingField::RdpClient => "Client tried first, others are fallbacks; mstsc on Windows",
+            SettingField::AutoSave => "Save every change immediately",
+        }
+    }
+
+    /// Whether the field cycles through fixed values instead of being typed.
+    pub fn is_choice(&self) -> bool {
+        matches!(
+            self,
+            SettingField::ProbeMethod
+                | SettingField::SshMode
+                | SettingField::RdpClient
+                | SettingField::AutoSave
+        )
+    }
+
+    /// The field's value for display.
+    pub fn display(&self, settings: &Settings) -> String {
+        match self {
+            SettingField::VpnTimeout => format!("{} seconds", settings.vpn_timeout_secs),
+            SettingField::PingTimeout => format!("{} ms", settings.ping_timeout_ms),
+            SettingField::PingRetries => settings.ping_retries.to_string(),
+            SettingField::ProbeMethod => settings.probe_method.name().to_string(),
+            SettingField::ProbePort => settings.probe_port.to_string(),
+            SettingField::TcpTimeout => format!("{} ms", settings.tcp_timeout_ms),
+            SettingField::StatusInterval => match settings.status_interval_secs {
+                0 => "Disabled".to_string(),
+                secs => format!("{} seconds", secs),
+            },
+            SettingField::SshMode => settings.ssh_mode.name().to_string(),
+            SettingField::RdpClient => settings.rdp_client.name().to_string(),
+            SettingField::AutoSave => if settings.auto_save { "On" } else { "Off" }.to_string(),
+        }
+    }
+
+    /// The field's value as edited, without units.
+    pub fn input(&self, settings: &Settings) -> String {
+        match self {
+            SettingField::VpnTimeout => settings.vpn_timeout_secs.to_string(),
+            SettingField::PingTimeout => settings.ping_timeout_ms.to_string(),
+            SettingField::PingRetries => settings.ping_retries.to_string(),
+            SettingField::ProbePort => settings.probe_port.to_string(),
+            SettingField::TcpTimeout => settings.tcp_timeout_ms.to_string(),
+            SettingField::StatusInterval => settings.status_interval_secs.to_string(),
+            _ => self.display(settings),
+        }
+    }
+
+    /// Validate `input` and store it in `settings`.
+    ///
+    /// On error `settings` is unchanged and the message says what is wrong.
+    pub fn apply(&self, settings: &mut Settings, input: &str) -> Result<(), String> {
+        match self {
+            SettingField::VpnTimeout => settings.vpn_timeout_secs = number(input, 1)?,
+            SettingField::PingTimeout => settings.ping_timeout_ms = number(input, 1)?,
+            SettingField::PingRetries => settings.ping_retries = number(input, 1)?,
+            SettingField::ProbePort => settings.probe_port = number(input, 1)?,
+            SettingField::TcpTimeout => settings.tcp_timeout_ms = number(input, 1)?,
+            SettingField::StatusInterval => settings.status_interval_secs = number(input, 0)?,
+            _ => return Err("is changed with ←/→".to_string()),
+        }
+        Ok(())
+    }
+
+    /// Switch a choice field to its next (or previous) value.
+    pub fn cycle(&self, settings: &mut Settings, forward: bool) {
+        match self {
+            SettingField::ProbeMethod => {
+                const METHODS: [ProbeMethod; 3] =
+                    [ProbeMethod::Icmp, ProbeMethod::Tcp, ProbeMethod::Both];
+                settings.probe_method = step(&METHODS, settings.probe_method, forward);
+            }
+            SettingField::SshMode => {
+                const MODES: [SshMode; 2] = [SshMode::External, SshMode::Embedded];
+                settings.ssh_mode = step(&MODES, settings.ssh_mode, forward);
+            }
+            SettingField::RdpClient => {
+                const CLIENTS: [RdpClient; 4] = [
+                    RdpClient::Auto,
+                    RdpClient::Xfreerdp,
+                    RdpClient::Xfreerdp3,
+                    RdpClient::Rdesktop,
+                ];
+                settings.rdp_client = step(&CLIENTS, settings.rdp_client, forward);
+            }
+            SettingField::AutoSave => settings.auto_save = !settings.auto_save,
+            _ => {}
+        }
+    }
+}
+
+/// Parse a whole number of at least `min`, within the range of `T`.
+fn number<T: TryFrom<u64>>(input: &str, min: u64) -> Result<T, String> {
+    let value: u64 = input
+        .trim()
+        .parse()
+        .map_err(|_| "must be a whole number".to_string())?;
+    if value < min {
+        return Err(format!("must be at least {}", min));
+    }
+    T::try_from(value).map_err(|_| "is too large".to_string())
+}
+
+/// The value after (or before) `current` in `values`, wrapping around.
+fn step<T: Copy + PartialEq>(values: &[T], current: T, forward: bool) -> T {
+    let index = values.iter().position(|&v| v == current).unwrap_or(0);
+    let next = if forward {
+        (index + 1) % values.len()
+    } else {
+        (index + values.len() - 1) % values.len()
+    };
+    values[next]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_validates_numbers() {
+        let mut settings = Settings::default();
+
+        assert!(SettingField::PingRetries
+            .apply(&mut settings, " 5 ")
+            .is_ok());
+        assert_eq!(settings.ping_retries, 5);
+
+        assert_eq!(
+            SettingField::PingRetries.apply(&mut settings, "five"),
+            Err("must be a whole number".to_string())
+        );
+        assert_eq!(
+            SettingField::VpnTimeout.apply(&mut settings, "0"),
+            Err("must be at least 1".to_string())
+        );
+        assert_eq!(
+            SettingField::ProbePort.apply(&mut settings, "70000"),
+            Err("is too large".to_string())
+        );
+        assert_eq!(settings.vpn_timeout_secs, 30);
+        assert_eq!(settings.probe_port, 3389);
+
+        // Zero turns the status refresh off
+        assert!(SettingField::StatusInterval
+            .apply(&mut settings, "0")
+            .is_ok());
+        assert_eq!(SettingField::StatusInterval.display(&settings), "Disabled");
+    }
+
+    #[test]
+    fn test_cycle_choices() {
+        let mut settings = Settings::default();
+
+        SettingField::ProbeMethod.cycle(&mut settings, false);
+        assert_eq!(settings.probe_method, ProbeMethod::Both);
+        SettingField::ProbeMethod.cycle(&mut settings, true);
+        assert_eq!(settings.probe_method, ProbeMethod::Icmp);
+
+        SettingField::SshMode.cycle(&mut settings, true);
+        assert_eq!(settings.ssh_mode, SshMode::Embedded);
+        SettingField::RdpClient.cycle(&mut settings, false);
+        assert_eq!(settings.rdp_client, RdpClient::Rdesktop);
+        assert_eq!(SettingField::RdpClient.display(&settings), "rdesktop");
+        SettingField::AutoSave.cycle(&mut settings, true);
+        assert!(settings.auto_save);
+
+        assert!(SettingField::AutoSave.apply(&mut settings, "off").is_err());
+    }
+}
@@ -0,0 +1,300 @@
+//! Background reachability probing for the server list.
+//!
+//! A worker thread runs a small tokio runtime that periodically TCP-probes
+//! every server's RDP and SSH endpoints (or its jump host) and reports the
+//! results over a channel, so the UI never blocks on the network.
+
+use crate::config::{Config, Server};
+use crate::platform;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinSet;
+use tracing::debug;
+
+/// Reachability of one endpoint.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Reachability {
+    /// Not probed yet (or not applicable).
+    #[default]
+    Unknown,
+    /// Accepted a TCP connection after the given latency.
+    Up(Duration),
+    /// Did not accept a connection within the timeout.
+    Down,
+}
+
+impl Reachability {
+    /// Short label for display, e.g. "up 12 ms".
+    pub fn label(&self) -> String {
+        match self {
+            Reachability::Unknown => "unknown".to_string(),
+            Reachability::Up(latency) => format!("up {} ms", latency.as_millis()),
+            Reachability::Down => "down".to_string(),
+        }
+    }
+}
+
+/// Last known status of a server.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ServerStatus {
+    /// RDP endpoint reachability.
+    pub rdp: Reachability,
+    /// SSH endpoint reachability (Unknown if the server has no SSH).
+    pub ssh: Reachability,
+    /// When the last probe finished.
+    pub checked: Option<Instant>,
+}
+
+impl ServerStatus {
+    /// Overall reachability: up if any endpoint is up.
+    pub fn overall(&self) -> Reachability {
+        match (self.rdp, self.ssh) {
+            (Reachability::Up(latency), _) | (_, Reachability::Up(latency)) => {
+                Reachability::Up(latency)
+            }
+            (Reachability::Down, _) | (_, Reachability::Down) => Reachability::Down,
+            _ => Reachability::Unknown,
+        }
+    }
+}
+
+/// Endpoints to probe for one server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ProbeTarget {
+    /// Index into `config.servers`.
+    index: usize,
+    /// RDP host and port.
+    rdp: (String, u16),
+    /// SSH host and port, if the server has SSH.
+    ssh: Option<(String, u16)>,
+}
+
+impl ProbeTarget {
+    /// Endpoints of a server; behind a jump host only the bastion is probed.
+    fn for_server(index: usize, server: &Server) -> Self {
+        if let Some(jump) = server.jump_host() {
+            let bastion = (jump.host.clone(), jump.port_or_default());
+            return Self {
+                index,
+                ssh: server.has_ssh().then(|| bastion.clone()),
+                rdp: bastion,
+            };
+        }
+
+        Self {
+            index,
+            rdp: (server.rdp.host.clone(), server.rdp.port_or_default()),
+            ssh: server
+                .ssh_config()
+                .map(|ssh| (ssh.host.clone(), ssh.port_or_default())),
+        }
+    }
+}
+
+/// Result of probing one server.
+#[derive(Debug)]
+struct ProbeResult {
+    /// Target generation the result belongs to.
+    generation: u64,
+    /// Index into `config.servers`.
+    index: usize,
+    /// Probed status.
+    status: ServerStatus,
+}
+
+/// Handle to the background probe worker. Dropping it stops the worker.
+pub struct StatusWorker {
+    targets: watch::Sender<(u64, Vec<ProbeTarget>)>,
+    results: mpsc::UnboundedReceiver<ProbeResult>,
+    generation: u64,
+}
+
+impl StatusWorker {
+    /// Spawn the worker probing every `interval`.
+    pub fn spawn(config: &Config, interval: Duration) -> std::io::Result<Self> {
+        let (targets_tx, targets_rx) = watch::channel((0, targets_for(config)));
+        let (results_tx, results_rx) = mpsc::unbounded_channel();
+        let timeout_ms = config.settings.tcp_timeout_ms;
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()?;
+
+        std::thread::Builder::new()
+            .name("status-probe".to_string())
+            .spawn(move || {
+                runtime.block_on(run(targets_rx, results_tx, interval, timeout_ms));
+                // Don't wait for probes still stuck in DNS or connect
+                runtime.shutdown_background();
+            })?;
+
+        Ok(Self {
+            targets: targets_tx,
+            results: results_rx,
+            generation: 0,
+        })
+    }
+
+    /// Replace the probed servers after the configuration changed.
+    pub fn update(&mut self, config: &Config) {
+        self.generation += 1;
+        let _ = self.targets.send((self.generation, targets_for(config)));
+    }
+
+    /// Apply results received since the last poll to `statuses`.
+    ///
+    /// Results from before the last `update` are discarded.
+    pub fn poll(&mut self, statuses: &mut Vec<ServerStatus>) {
+        while let Ok(result) = self.results.try_recv() {
+            if result.generation != self.generation {
+                continue;
+            }
+            if statuses.len() <= result.index {
+                statuses.resize(result.index + 1, ServerStatus::default());
+            }
+            statuses[result.index] = result.status;
+        }
+    }
+}
+
+/// Probe targets for all configured servers.
+fn targets_for(config: &Config) -> Vec<ProbeTarget> {
+    config
+        .servers
+        .iter()
+        .enumerate()
+        .map(|(index, server)| ProbeTarget::for_server(index, server))
+        .collect()
+}
+
+/// Worker loop: probe all targets, then sleep until the next round or a
+/// target update. Ends when the `StatusWorker` is dropped.
+async fn run(
+    mut targets: watch::Receiver<(u64, Vec<ProbeTarget>)>,
+    results: mpsc::UnboundedSender<ProbeResult>,
+    interval: Duration,
+    timeout_ms: u32,
+) {
+    loop {
+        let (generation, current) = targets.borrow_and_update().clone();
+        debug!("Probing {} servers", current.len());
+
+        let mut probes = JoinSet::new();
+        for target in current {
+            probes.spawn_blocking(move || ProbeResult {
+                generation,
+                index: target.index,
+                status: probe_target(&target, timeout_ms),
+            });
+        }
+
+        let mut updated = false;
+        loop {
+            tokio::select! {
+                probe = probes.join_next() => match probe {
+                    Some(Ok(result)) => {
+                        if results.send(result).is_err() {
+                            return;
+                        }
+                    }
+                    Some(Err(_)) => {}
+                    None => break,
+                },
+                changed = targets.changed() => {
+                    if changed.is_err() {
+                        return;
+                    }
+                    updated = true;
+                    break;
+                }
+            }
+        }
+
+        if updated {
+            // Start over with the new targets
+            continue;
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            changed = targets.changed() => {
+                if changed.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Probe the endpoints of one server.
+fn probe_target(target: &ProbeTarget, timeout_ms: u32) -> ServerStatus {
+    let rdp = probe_endpoint(&target.rdp, timeout_ms);
+    let ssh = match &target.ssh {
+        // Same endpoint as RDP behind a jump host, no need to probe twice
+        Some(endpoint) if *endpoint == target.rdp => rdp,
+        Some(endpoint) => probe_endpoint(endpoint, timeout_ms),
+        None => Reachability::Unknown,
+    };
+
+    ServerStatus {
+        rdp,
+        ssh,
+        checked: Some(Instant::now()),
+    }
+}
+
+/// TCP-probe one endpoint, measuring connect latency.
+fn probe_endpoint((host, port): &(String, u16), timeout_ms: u32) -> Reachability {
+    let start = Instant::now();
+    if platform::tcp_probe(host, *port, timeout_ms) {
+        Reachability::Up(start.elapsed())
+    } else {
+        Reachability::Down
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_overall_status() {
+        let up = Reachability::Up(Duration::from_millis(5));
+        let status = ServerStatus {
+            rdp: Reachability::Down,
+            ssh: up,
+            checked: None,
+        };
+        assert_eq!(status.overall(), up);
+
+        let status = ServerStatus {
+            rdp: Reachability::Down,
+            ..Default::default()
+        };
+        assert_eq!(status.overall(), Reachability::Down);
+        assert_eq!(ServerStatus::default().overall(), Reachability::Unknown);
+    }
+
+    #[test]
+    fn test_worker_reports_status() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let mut config = Config::default_config();
+        config.servers.truncate(1);
+        config.servers[0].rdp = format!("127.0.0.1:{}", port).parse().unwrap();
+        config.servers[0].ssh = None;
+
+        let mut worker = StatusWorker::spawn(&config, Duration::from_secs(60)).unwrap();
+        let mut statuses = Vec::new();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while statuses.is_empty() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(20));
+            worker.poll(&mut statuses);
+        }
+
+        assert!(matches!(statuses[0].rdp, Reachability::Up(_)));
+        assert_eq!(statuses[0].ssh, Reachability::Unknown);
+    }
+}
@@ -1,13 +1,18 @@
 //! UI rendering for the TUI.
 
-use super::app::{App, ConfirmAction, ConnectionStatus, Screen};
+use super::app::{App, ConfirmAction, ConnectionStatus, ListRow, Screen};
+use super::pty::PtySession;
+use super::search;
+use super::settings::SettingField;
+use super::status::Reachability;
 use crate::connection::ConnectionType;
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style, Stylize},
     text::{Line, Span},
     widgets::{
-        Block, BorderType, Borders, Clear, List, ListItem, Padding, Paragraph, Row, Table, Wrap,
+        Block, BorderType, Borders, Clear, List, ListItem, ListState, Padding, Paragraph, Row,
+        Table, Wrap,
     },
     Frame,
 };
@@ -54,28 +59,42 @@ fn render_header(app: &App, frame: &mut Frame, area: Rect) {
             }
         }
         Screen::Confirm => " Confirm ",
+        Screen::Sessions => " Sessions ",
     };
 
-    let status_indicator = match &app.connection_status {
-        ConnectionStatus::Idle => Span::styled("●", Style::default().fg(Color::Gray)),
-        ConnectionStatus::ConnectingVpn | ConnectionStatus::WaitingForVpn => {
-            Span::styled("●", Style::default().fg(Color::Yellow))
+    // The shown session's status, or green if any session is up
+    let status = match app.active() {
+        Some(session) => &session.status,
+        None if app
+            .sessions
+            .iter()
+            .any(|s| s.status == ConnectionStatus::Connected) =>
+        {
+            &ConnectionStatus::Connected
         }
-        ConnectionStatus::CheckingConnectivity | ConnectionStatus::StartingSession => {
-            Span::styled("●", Style::default().fg(Color::Cyan))
-        }
-        ConnectionStatus::Connected => Span::styled("●", Style::default().fg(Color::Green)),
-        ConnectionStatus::Disconnecting => Span::styled("●", Style::default().fg(Color::Yellow)),
-        ConnectionStatus::Error(_) => Span::styled("●", Style::default().fg(Color::Red)),
+        None => &ConnectionStatus::Idle,
     };
 
-    let header_text = Line::from(vec![
+    let mut header_spans = vec![
         Span::raw(" "),
-        status_indicator,
+        status_dot(status),
         Span::raw(" "),
         Span::styled(title, Style::default().fg(Color::Cyan).bold()),
         Span::raw(" v2.1.0"),
-    ]);
+    ];
+    if !app.sessions.is_empty() {
+        header_spans.push(Span::styled(
+            format!("  [{} session(s)]", app.sessions.len()),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    if app.modified {
+        header_spans.push(Span::styled(
+            "  [modified]",
+            Style::default().fg(Color::Yellow),
+        ));
+    }
+    let header_text = Line::from(header_spans);
 
     let header = Paragraph::new(header_text)
         .alignment(Alignment::Center)
@@ -89,6 +108,19 @@ fn render_header(app: &App, frame: &mut Frame, area: Rect) {
     frame.render_widget(header, area);
 }
 
+/// Colored dot for a connection status.
+fn status_dot(status: &ConnectionStatus) -> Span<'static> {
+    let color = match status {
+        ConnectionStatus::Idle => Color::Gray,
+        ConnectionStatus::ConnectingVpn | ConnectionStatus::WaitingForVpn => Color::Yellow,
+        ConnectionStatus::CheckingConnectivity | ConnectionStatus::StartingSession => Color::Cyan,
+        ConnectionStatus::Connected => Color::Green,
+        ConnectionStatus::Disconnecting => Color::Yellow,
+        ConnectionStatus::Error(_) => Color::Red,
+    };
+    Span::styled("●", Style::default().fg(color))
+}
+
 fn render_content(app: &App, frame: &mut Frame, area: Rect) {
     match app.screen {
         Screen::ServerList => render_server_list(app, frame, area),
@@ -97,6 +129,7 @@ fn render_content(app: &App, frame: &mut Frame, area: Rect) {
         Screen::Connected => render_connected(app, frame, area),
         Screen::Settings => render_settings(app, frame, area),
         Screen::EditServer => render_edit_server(app, frame, area),
+        Screen::Sessions => render_sessions(app, frame, area),
         Screen::Help | Screen::Confirm => {
             // These are rendered as popups, show server list behind
             render_server_list(app, frame, area);
@@ -110,55 +143,131 @@ fn render_server_list(app: &App, frame: &mut Frame, area: Rect) {
         .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
         .split(area);
 
-    // Server list
-    let items: Vec<ListItem> = app
-        .config
-        .servers
+    // Server list, as a tree when servers are grouped
+    let rows = app.server_rows();
+    let items: Vec<ListItem> = rows
         .iter()
-        .enumerate()
-        .map(|(i, server)| {
-            let ssh_indicator = if server.has_ssh() {
-                Span::styled(" [SSH]", Style::default().fg(Color::Green))
-            } else {
-                Span::styled(" [RDP]", Style::default().fg(Color::Yellow))
-            };
-
-            let prefix = format!(" {}. ", i + 1);
-            let line = Line::from(vec![
-                Span::styled(prefix, Style::default().fg(Color::DarkGray)),
-                Span::raw(&server.name),
-                ssh_indicator,
-            ]);
-
-            if i == app.selected_server {
-                ListItem::new(line).style(
-                    Style::default()
-                        .bg(Color::Blue)
-                        .fg(Color::White)
-                        .add_modifier(Modifier::BOLD),
-                )
-            } else {
+        .map(|row| match row {
+            ListRow::Group(group) => {
+                let collapsed = app.collapsed_groups.contains(group);
+                let count = app
+                    .config
+                    .servers
+                    .iter()
+                    .filter(|s| s.group_name() == group.as_deref())
+                    .count();
+                let line = Line::from(vec![
+                    Span::styled(
+                        if collapsed { "▶ " } else { "▼ " },
+                        Style::default().fg(Color::Cyan),
+                    ),
+                    Span::styled(
+                        group.as_deref().unwrap_or("Ungrouped"),
+                        Style::default().fg(Color::Cyan).bold(),
+                    ),
+                    Span::styled(
+                        format!(" ({})", count),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ]);
                 ListItem::new(line)
             }
+            ListRow::Server(i) => {
+                let server = &app.config.servers[*i];
+                let ssh_indicator = if server.has_ssh() {
+                    Span::styled(" [SSH]", Style::default().fg(Color::Green))
+                } else {
+                    Span::styled(" [RDP]", Style::default().fg(Color::Yellow))
+                };
+
+                let indent = if matches!(rows.first(), Some(ListRow::Group(_))) {
+                    "  "
+                } else {
+                    ""
+                };
+                let status = app.server_status(*i).overall();
+                let prefix = format!(" {}. ", i + 1);
+                let mut spans = vec![
+                    Span::raw(indent),
+                    reachability_dot(status),
+                    Span::styled(prefix, Style::default().fg(Color::DarkGray)),
+                ];
+                spans.extend(highlight_matches(&server.name, &app.search_query));
+                spans.push(ssh_indicator);
+                if let Reachability::Up(latency) = status {
+                    spans.push(Span::styled(
+                        format!(" {} ms", latency.as_millis()),
+                        Style::default().fg(Color::DarkGray),
+                    ));
+                }
+                ListItem::new(Line::from(spans))
+            }
         })
         .collect();
 
+    let title = if app.search_active || !app.search_query.is_empty() {
+        let cursor = if app.search_active { "│" } else { "" };
+        format!(" Servers /{}{} ", app.search_query, cursor)
+    } else {
+        " Servers ".to_string()
+    };
+
     let list = List::new(items)
         .block(
             Block::default()
-                .title(" Servers ")
+                .title(title)
                 .borders(Borders::ALL)
                 .border_type(BorderType::Rounded)
                 .padding(Padding::horizontal(1)),
         )
-        .highlight_style(Style::default().bg(Color::Blue));
+        .highlight_style(
+            Style::default()
+                .bg(Color::Blue)
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD),
+        );
 
-    frame.render_widget(list, layout[0]);
+    let mut state = ListState::default().with_selected(Some(app.selected_row));
+    frame.render_stateful_widget(list, layout[0], &mut state);
 
     // Server details panel
     render_server_details(app, frame, layout[1]);
 }
 
+/// Colored status dot for a reachability state.
+fn reachability_dot(reachability: Reachability) -> Span<'static> {
+    let color = match reachability {
+        Reachability::Unknown => Color::DarkGray,
+        Reachability::Up(_) => Color::Green,
+        Reachability::Down => Color::Red,
+    };
+    Span::styled("●", Style::default().fg(color))
+}
+
+/// Split text into spans, highlighting characters matched by the search query.
+fn highlight_matches<'a>(text: &'a str, query: &str) -> Vec<Span<'a>> {
+    if query.is_empty() {
+        return vec![Span::raw(text)];
+    }
+
+    let indices = search::match_indices(text, query);
+    text.chars()
+        .enumerate()
+        .map(|(i, c)| {
+            if indices.contains(&i) {
+                Span::styled(
+                    c.to_string(),
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                )
+            } else {
+                Span::raw(c.to_string())
+            }
+        })
+        .collect()
+}
+
 fn render_server_details(app: &App, frame: &mut Frame, area: Rect) {
     let block = Block::default()
         .title(" Server Details ")
@@ -167,14 +276,49 @@ fn render_server_details(app: &App, frame: &mut Frame, area: Rect) {
         .padding(Padding::uniform(1));
 
     if let Some(server) = app.current_server() {
-        let rows = vec![
+        let ssh = server
+            .ssh_string()
+            .unwrap_or_else(|| "Not available".to_string());
+        let rdp = server.rdp.to_string();
+        let vpn = if server.has_vpn() {
+            &server.vpn
+        } else {
+            "None"
+        };
+        let mut rows = vec![
             Row::new(vec!["Name:", &server.name]),
-            Row::new(vec!["VPN:", &server.vpn]),
-            Row::new(vec!["RDP:", &server.rdp]),
-            Row::new(vec!["SSH:", server.ssh_string().unwrap_or("Not available")]),
+            Row::new(vec!["VPN:", vpn]),
+            Row::new(vec!["RDP:", rdp.as_str()]),
+            Row::new(vec!["SSH:", ssh.as_str()]),
         ];
+        let jump = server.jump_host().map(|jump| jump.to_string());
+        if let Some(jump) = &jump {
+            rows.push(Row::new(vec!["Jump:", jump.as_str()]));
+        }
+        if let Some(group) = server.group_name() {
+            rows.push(Row::new(vec!["Group:", group]));
+        }
+        let tags = server.tags.join(", ");
+        if !tags.is_empty() {
+            rows.push(Row::new(vec!["Tags:", tags.as_str()]));
+        }
+
+        let status = app.server_status(app.selected_server);
+        let mut status_text = format!("RDP {}", status.rdp.label());
+        if server.has_ssh() {
+            status_text.push_str(&format!(", SSH {}", status.ssh.label()));
+        }
+        if server.jump_host().is_some() {
+            status_text.push_str(" (jump host)");
+        }
+        let checked = match status.checked {
+            Some(at) => format!("{}s ago", at.elapsed().as_secs()),
+            None => "never".to_string(),
+        };
+        rows.push(Row::new(vec!["Status:", status_text.as_str()]));
+        rows.push(Row::new(vec!["Probed:", checked.as_str()]));
 
-        let widths = [Constraint::Length(6), Constraint::Min(10)];
+        let widths = [Constraint::Length(7), Constraint::Min(10)];
 
         let table = Table::new(rows, widths)
             .block(block)
@@ -246,33 +390,56 @@ fn render_connection_type(app: &App, frame: &mut Frame, area: Rect) {
 fn render_connecting(app: &App, frame: &mut Frame, area: Rect) {
     let centered = centered_rect(60, 50, area);
 
-    let status_text = match &app.connection_status {
-        ConnectionStatus::ConnectingVpn => "Initiating VPN connection...",
-        ConnectionStatus::WaitingForVpn => "Waiting for VPN to establish...",
-        ConnectionStatus::CheckingConnectivity => "Checking connectivity...",
-        ConnectionStatus::StartingSession => "Starting session...",
-        ConnectionStatus::Error(msg) => msg.as_str(),
+    let session = app.active();
+    let status_text = match session.map(|s| &s.status) {
+        Some(ConnectionStatus::ConnectingVpn) => "Initiating VPN connection...",
+        Some(ConnectionStatus::WaitingForVpn) => "Waiting for VPN to establish...",
+        Some(ConnectionStatus::CheckingConnectivity) => "Checking connectivity...",
+        Some(ConnectionStatus::StartingSession) => "Starting session...",
         _ => "Connecting...",
     };
 
-    let spinner = get_spinner_frame();
+    let error = match session.map(|s| &s.status) {
+        Some(ConnectionStatus::Error(error)) => Some(error),
+        _ => None,
+    };
+    let failed = error.is_some();
+    let (spinner, status_color, exit_hint) = if failed {
+        ("✗", Color::Red, "Press ESC to go back")
+    } else {
+        (get_spinner_frame(), Color::Yellow, "Press ESC to cancel")
+    };
 
     let elapsed = app
         .connection_duration()
         .map(App::format_duration)
         .unwrap_or_else(|| "00:00".to_string());
 
-    let lines = vec![
+    let mut lines = vec![
         Line::from(""),
         Line::from(Span::styled(
             format!(" {} ", spinner),
             Style::default().fg(Color::Cyan),
         )),
         Line::from(""),
-        Line::from(Span::styled(
+    ];
+    match error {
+        Some(error) => {
+            lines.push(Line::from(Span::styled(
+                error.to_string(),
+                Style::default().fg(status_color),
+            )));
+            lines.push(Line::from(Span::styled(
+                error.hint(),
+                Style::default().fg(Color::White),
+            )));
+        }
+        None => lines.push(Line::from(Span::styled(
             status_text,
-            Style::default().fg(Color::Yellow),
-        )),
+            Style::default().fg(status_color),
+        ))),
+    }
+    lines.extend([
         Line::from(""),
         Line::from(Span::styled(
             format!("Elapsed: {}", elapsed),
@@ -280,15 +447,12 @@ fn render_connecting(app: &App, frame: &mut Frame, area: Rect) {
         )),
         Line::from(""),
         Line::from(Span::styled(
-            "Press ESC to cancel",
+            exit_hint,
             Style::default().fg(Color::DarkGray),
         )),
-    ];
+    ]);
 
-    let server_name = app
-        .current_server()
-        .map(|s| s.name.as_str())
-        .unwrap_or("Unknown");
+    let server_name = session.map(|s| s.server.name.as_str()).unwrap_or("Unknown");
 
     let paragraph = Paragraph::new(lines)
         .block(
@@ -296,9 +460,10 @@ fn render_connecting(app: &App, frame: &mut Frame, area: Rect) {
                 .title(format!(" Connecting to {} ", server_name))
                 .borders(Borders::ALL)
                 .border_type(BorderType::Rounded)
-                .border_style(Style::default().fg(Color::Yellow)),
+                .border_style(Style::default().fg(status_color)),
         )
-        .alignment(Alignment::Center);
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true });
 
     frame.render_widget(Clear, centered);
     frame.render_widget(paragraph, centered);
@@ -312,11 +477,12 @@ fn render_connected(app: &App, frame: &mut Frame, area: Rect) {
         .map(App::format_duration)
         .unwrap_or_else(|| "00:00".to_string());
 
-    let server = app.current_server();
-    let server_name = server.map(|s| s.name.as_str()).unwrap_or("Unknown");
-    let vpn_name = server.map(|s| s.vpn.as_str()).unwrap_or("Unknown");
-
-    let conn_type = app.selected_connection_type();
+    let Some(session) = app.active() else {
+        return;
+    };
+    let server_name = session.server.name.as_str();
+    let vpn_name = session.vpn.as_deref().unwrap_or("None");
+    let conn_type = session.conn_type;
 
     let lines = vec![
         Line::from(""),
@@ -343,7 +509,7 @@ fn render_connected(app: &App, frame: &mut Frame, area: Rect) {
         ]),
         Line::from(""),
         Line::from(Span::styled(
-            "Press D to disconnect, ESC to return",
+            "Press S for SSH, D to disconnect, W for sessions, ESC to return",
             Style::default().fg(Color::DarkGray),
         )),
     ];
@@ -358,42 +524,248 @@ fn render_connected(app: &App, frame: &mut Frame, area: Rect) {
         )
         .alignment(Alignment::Center);
 
-    frame.render_widget(Clear, centered);
-    frame.render_widget(paragraph, centered);
+    let Some(pty) = &session.ssh else {
+        frame.render_widget(Clear, centered);
+        frame.render_widget(paragraph, centered);
+        return;
+    };
+
+    // Embedded SSH: session info on the left, terminal on the right
+    let layout = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Length(40), Constraint::Min(20)])
+        .split(area);
+
+    frame.render_widget(paragraph, layout[0]);
+    render_terminal(app, pty, frame, layout[1]);
+}
+
+/// Render the embedded SSH terminal pane.
+fn render_terminal(app: &App, pty: &PtySession, frame: &mut Frame, area: Rect) {
+    let border_color = if app.pty_focused {
+        Color::Cyan
+    } else {
+        Color::DarkGray
+    };
+    let block = Block::default()
+        .title(" SSH ")
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(border_color));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    pty.resize(inner.height, inner.width);
+    let rendered = pty.with_screen(|screen| {
+        let lines: Vec<Line> = (0..inner.height)
+            .map(|row| terminal_line(screen, row, inner.width))
+            .collect();
+        (lines, screen.cursor_position(), screen.hide_cursor())
+    });
+    let Some((lines, (cursor_row, cursor_col), hide_cursor)) = rendered else {
+        return;
+    };
+
+    frame.render_widget(Paragraph::new(lines), inner);
+    if app.pty_focused && !hide_cursor && cursor_row < inner.height && cursor_col < inner.width {
+        frame.set_cursor_position((inner.x + cursor_col, inner.y + cursor_row));
+    }
+}
+
+/// Convert one row of the terminal emulator screen to a styled line.
+fn terminal_line(screen: &vt100::Screen, row: u16, width: u16) -> Line<'static> {
+    let mut spans = Vec::new();
+    for col in 0..width {
+        let Some(cell) = screen.cell(row, col) else {
+            break;
+        };
+        // The left half of a wide character already covers this column
+        if cell.is_wide_continuation() {
+            continue;
+        }
+
+        let mut style = Style::default()
+            .fg(terminal_color(cell.fgcolor()))
+            .bg(terminal_color(cell.bgcolor()));
+        if cell.bold() {
+            style = style.add_modifier(Modifier::BOLD);
+        }
+        if cell.italic() {
+            style = style.add_modifier(Modifier::ITALIC);
+        }
+        if cell.underline() {
+            style = style.add_modifier(Modifier::UNDERLINED);
+        }
+        if cell.inverse() {
+            style = style.add_modifier(Modifier::REVERSED);
+        }
+
+        let contents = if cell.has_contents() {
+            cell.contents()
+        } else {
+            " ".to_string()
+        };
+        spans.push(Span::styled(contents, style));
+    }
+    Line::from(spans)
+}
+
+/// Map a terminal emulator color to a ratatui color.
+fn terminal_color(color: vt100::Color) -> Color {
+    match color {
+        vt100::Color::Default => Color::Reset,
+        vt100::Color::Idx(index) => Color::Indexed(index),
+        vt100::Color::Rgb(r, g, b) => Color::Rgb(r, g, b),
+    }
+}
+
+fn render_sessions(app: &App, frame: &mut Frame, area: Rect) {
+    let block = Block::default()
+        .title(" Sessions ")
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .padding(Padding::horizontal(1));
+
+    if app.sessions.is_empty() {
+        let text = Paragraph::new("No active sessions")
+            .block(block)
+            .alignment(Alignment::Center);
+        frame.render_widget(text, area);
+        return;
+    }
+
+    let header = Row::new(vec![
+        "", "Server", "VPN", "Type", "Status", "RDP PID", "SSH", "Duration",
+    ])
+    .style(Style::default().fg(Color::DarkGray).bold());
+
+    let rows: Vec<Row> = app
+        .sessions
+        .iter()
+        .enumerate()
+        .map(|(i, session)| {
+            let status = match &session.status {
+                ConnectionStatus::Idle => "Idle".to_string(),
+                ConnectionStatus::ConnectingVpn => "Connecting VPN".to_string(),
+                ConnectionStatus::WaitingForVpn => "Waiting for VPN".to_string(),
+                ConnectionStatus::CheckingConnectivity => "Checking".to_string(),
+                ConnectionStatus::StartingSession => "Starting".to_string(),
+                ConnectionStatus::Connected => "Connected".to_string(),
+                ConnectionStatus::Disconnecting => "Disconnecting".to_string(),
+                ConnectionStatus::Error(error) => format!("Error: {}", error),
+            };
+            let ssh = if session.ssh.is_some() {
+                "embedded"
+            } else {
+                "-"
+            };
+
+            let row = Row::new(vec![
+                Line::from(status_dot(&session.status)),
+                Line::from(session.server.name.clone()),
+                Line::from(match &session.vpn {
+                    Some(vpn) if app.sessions.vpn_users(vpn) > 1 => format!("{} (shared)", vpn),
+                    Some(vpn) => vpn.clone(),
+                    None => "-".to_string(),
+                }),
+                Line::from(session.conn_type.name()),
+                Line::from(status),
+                Line::from(
+                    session
+                        .rdp_pid
+                        .map(|pid| pid.to_string())
+                        .unwrap_or_else(|| "-".to_string()),
+                ),
+                Line::from(ssh),
+                Line::from(App::format_duration(session.duration())),
+            ]);
+
+            if i == app.selected_session {
+                row.style(Style::default().bg(Color::Blue).fg(Color::White).bold())
+            } else {
+                row
+            }
+        })
+        .collect();
+
+    let widths = [
+        Constraint::Length(1),
+        Constraint::Percentage(20),
+        Constraint::Percentage(18),
+        Constraint::Length(4),
+        Constraint::Min(12),
+        Constraint::Length(8),
+        Constraint::Length(8),
+        Constraint::Length(8),
+    ];
+
+    let table = Table::new(rows, widths).header(header).block(block);
+    frame.render_widget(table, area);
 }
 
 fn render_settings(app: &App, frame: &mut Frame, area: Rect) {
     let settings = &app.config.settings;
+    let selected = app.selected_setting_field();
 
-    let vpn_timeout_str = format!("{} seconds", settings.vpn_timeout_secs);
-    let ping_timeout_str = format!("{} ms", settings.ping_timeout_ms);
-    let ping_retries_str = settings.ping_retries.to_string();
+    let mut lines = Vec::new();
+    for field in SettingField::ALL {
+        let is_selected = field == selected;
+        let label_style = if is_selected {
+            Style::default().fg(Color::Cyan).bold()
+        } else {
+            Style::default().fg(Color::DarkGray)
+        };
 
-    let rows = vec![
-        Row::new(vec!["VPN Timeout", vpn_timeout_str.as_str()]),
-        Row::new(vec!["Ping Timeout", ping_timeout_str.as_str()]),
-        Row::new(vec!["Ping Retries", ping_retries_str.as_str()]),
-    ];
+        let mut spans = vec![Span::styled(format!(" {:<18}", field.label()), label_style)];
+        if is_selected && app.settings_editing {
+            spans.push(Span::styled(
+                app.input_buffer.as_str(),
+                Style::default().fg(Color::White).bg(Color::DarkGray),
+            ));
+            spans.push(Span::styled("│", Style::default().fg(Color::Cyan)));
+        } else if is_selected && field.is_choice() {
+            spans.push(Span::styled(
+                format!("◀ {} ▶", field.display(settings)),
+                Style::default().fg(Color::White).bold(),
+            ));
+        } else {
+            let value_style = if is_selected {
+                Style::default().fg(Color::White).bold()
+            } else {
+                Style::default().fg(Color::White)
+            };
+            spans.push(Span::styled(field.display(settings), value_style));
+        }
+        lines.push(Line::from(spans));
+    }
 
-    let widths = [Constraint::Length(20), Constraint::Min(10)];
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        format!(" {}", selected.hint()),
+        Style::default().fg(Color::DarkGray).italic(),
+    )));
+    if let Some(error) = &app.settings_error {
+        lines.push(Line::from(Span::styled(
+            format!(" ✗ {}", error),
+            Style::default().fg(Color::Red),
+        )));
+    }
 
-    let table = Table::new(rows, widths)
-        .block(
-            Block::default()
-                .title(" Settings ")
-                .borders(Borders::ALL)
-                .border_type(BorderType::Rounded)
-                .padding(Padding::uniform(1)),
-        )
-        .column_spacing(2);
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .title(" Settings ")
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .padding(Padding::uniform(1)),
+    );
 
-    let centered = centered_rect(60, 50, area);
+    let centered = centered_rect(60, 60, area);
     frame.render_widget(Clear, centered);
-    frame.render_widget(table, centered);
+    frame.render_widget(paragraph, centered);
 }
 
 fn render_edit_server(app: &App, frame: &mut Frame, area: Rect) {
-    let centered = centered_rect(70, 60, area);
+    let centered = centered_rect(70, 80, area);
 
     let fields = [
         ("Name", &app.edit_server_fields.name, "Server display name"),
@@ -401,15 +773,26 @@ fn render_edit_server(app: &App, frame: &mut Frame, area: Rect) {
         (
             "SSH (optional)",
             &app.edit_server_fields.ssh,
-            "user@host format",
+            "user@host[:port] format",
         ),
         (
             "VPN Name",
             &app.edit_server_fields.vpn,
             "As configured in OS",
         ),
+        (
+            "Group (optional)",
+            &app.edit_server_fields.group,
+            "e.g. customer name",
+        ),
+        (
+            "Tags (optional)",
+            &app.edit_server_fields.tags,
+            "Comma-separated",
+        ),
     ];
 
+    let errors = app.edit_server_errors();
     let mut lines = vec![Line::from("")];
 
     for (i, (label, value, hint)) in fields.iter().enumerate() {
@@ -421,10 +804,15 @@ fn render_edit_server(app: &App, frame: &mut Frame, area: Rect) {
             value
         };
 
-        let label_style = if is_selected {
-            Style::default().fg(Color::Cyan).bold()
-        } else {
-            Style::default().fg(Color::DarkGray)
+        // Empty required fields are only flagged once saving was attempted
+        let error = errors[i]
+            .as_ref()
+            .filter(|_| app.edit_submitted || !display_value.trim().is_empty());
+
+        let label_style = match (error, is_selected) {
+            (Some(_), _) => Style::default().fg(Color::Red).bold(),
+            (None, true) => Style::default().fg(Color::Cyan).bold(),
+            (None, false) => Style::default().fg(Color::DarkGray),
         };
 
         let value_style = if is_selected {
@@ -445,10 +833,16 @@ fn render_edit_server(app: &App, frame: &mut Frame, area: Rect) {
             Span::styled(cursor_indicator, Style::default().fg(Color::Cyan)),
         ]));
 
-        lines.push(Line::from(vec![Span::styled(
-            format!("   {}", hint),
-            Style::default().fg(Color::DarkGray).italic(),
-        )]));
+        lines.push(match error {
+            Some(error) => Line::from(Span::styled(
+                format!("   ✗ {}", error),
+                Style::default().fg(Color::Red),
+            )),
+            None => Line::from(Span::styled(
+                format!("   {}", hint),
+                Style::default().fg(Color::DarkGray).italic(),
+            )),
+        });
 
         lines.push(Line::from(""));
     }
@@ -492,13 +886,30 @@ fn render_confirm_dialog(app: &App, frame: &mut Frame, area: Rect) {
                 format!("Are you sure you want to delete '{}'?", name),
             )
         }
-        Some(ConfirmAction::Disconnect) => (
-            " Disconnect ",
-            "Are you sure you want to disconnect?".to_string(),
-        ),
+        Some(ConfirmAction::Disconnect(id)) => {
+            let name = app
+                .sessions
+                .get(*id)
+                .map(|s| s.server.name.as_str())
+                .unwrap_or("this server");
+            (
+                " Disconnect ",
+                format!("Are you sure you want to disconnect from '{}'?", name),
+            )
+        }
         Some(ConfirmAction::Quit) => (
             " Quit ",
-            "You are connected. Quit and disconnect?".to_string(),
+            format!(
+                "You have {} active session(s). Quit and disconnect?",
+                app.sessions.len()
+            ),
+        ),
+        Some(ConfirmAction::SaveChanges) => (
+            " Unsaved Changes ",
+            format!(
+                "Save changes to '{}' before quitting? (ESC to keep editing)",
+                app.config_path.display()
+            ),
         ),
         None => (" Confirm ", "Confirm action?".to_string()),
     };
@@ -561,7 +972,9 @@ fn render_help_popup(app: &App, frame: &mut Frame, area: Rect) {
         Line::from("  ↓/j      Move selection down"),
         Line::from("  Enter    Confirm selection"),
         Line::from("  ESC      Go back / Cancel"),
+        Line::from("  ←/h →/l  Collapse / expand group"),
         Line::from("  1-9      Quick select server by number"),
+        Line::from("  /        Search servers (name, host, VPN, tags)"),
         Line::from(""),
         Line::from(Span::styled(
             "Server Management",
@@ -570,6 +983,9 @@ fn render_help_popup(app: &App, frame: &mut Frame, area: Rect) {
         Line::from("  a        Add new server"),
         Line::from("  e        Edit selected server"),
         Line::from("  d/Del    Delete selected server"),
+        Line::from("  K/J      Move selected server up / down"),
+        Line::from("  u        Undo last change"),
+        Line::from("  Ctrl+R   Redo"),
         Line::from(""),
         Line::from(Span::styled(
             "Quick Connect",
@@ -584,6 +1000,8 @@ fn render_help_popup(app: &App, frame: &mut Frame, area: Rect) {
         )),
         Line::from("  ?/F1     Show this help"),
         Line::from("  s        Settings"),
+        Line::from("  S        Save configuration (in Settings)"),
+        Line::from("  w        Sessions"),
         Line::from("  q        Quit"),
         Line::from("  Ctrl+C   Force quit"),
         Line::from(""),
@@ -591,8 +1009,11 @@ fn render_help_popup(app: &App, frame: &mut Frame, area: Rect) {
             "While Connected",
             Style::default().fg(Color::Cyan).bold(),
         )),
+        Line::from("  s        Open SSH session"),
+        Line::from("  F12      Focus/unfocus embedded terminal"),
         Line::from("  d        Disconnect"),
-        Line::from("  ESC      Return to menu"),
+        Line::from("  w        Switch session"),
+        Line::from("  ESC      Return to menu (session keeps running)"),
         Line::from(""),
         Line::from(Span::styled(
             "Press any key to close",
@@ -618,28 +1039,56 @@ fn render_help_popup(app: &App, frame: &mut Frame, area: Rect) {
 
 fn render_footer(app: &App, frame: &mut Frame, area: Rect) {
     let shortcuts = match app.screen {
+        Screen::ServerList if app.search_active => {
+            "Type to search | ↑↓:Navigate | Enter:Connect | ESC:Clear search"
+        }
         Screen::ServerList => {
-            "↑↓:Navigate | Enter:Connect | a:Add | e:Edit | d:Delete | ?:Help | q:Quit"
+            "↑↓:Navigate | ←→:Collapse/Expand | /:Search | Enter:Connect | a:Add | e:Edit | d:Delete | K/J:Move | u:Undo | w:Sessions | ?:Help | q:Quit"
         }
         Screen::ConnectionTypeSelect => "↑↓:Navigate | Enter:Select | ESC:Back",
         Screen::Connecting => "ESC:Cancel",
-        Screen::Connected => "d:Disconnect | ESC:Menu",
+        Screen::Connected if app.pty_focused => "F12:Unfocus terminal",
+        Screen::Connected if app.pty().is_some() => {
+            "F12:Focus terminal | d:Disconnect | w:Sessions | ESC:Menu"
+        }
+        Screen::Connected => "s:SSH | d:Disconnect | w:Sessions | ESC:Menu",
+        Screen::Sessions => "↑↓:Navigate | Enter:Switch | d:Disconnect | ESC:Back",
         Screen::EditServer => "Tab:Next | Enter:Save | ESC:Cancel",
-        Screen::Settings => "S:Save | ESC:Back",
+        Screen::Settings if app.settings_editing => "Enter:Apply | ESC:Cancel",
+        Screen::Settings => "↑↓:Navigate | Enter:Edit | ←→:Change | S:Save | ESC:Back",
         Screen::Help => "ESC:Close",
         Screen::Confirm => "←→:Select | Enter:Confirm | ESC:Cancel",
     };
 
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(Color::DarkGray));
+
+    // Last config change, with the history keys that currently apply
+    if let Some(action) = &app.last_action {
+        let mut keys = Vec::new();
+        if app.history.can_undo() {
+            keys.push("u:Undo");
+        }
+        if app.history.can_redo() {
+            keys.push("Ctrl+R:Redo");
+        }
+        let text = if keys.is_empty() {
+            format!(" {} ", action)
+        } else {
+            format!(" {} ({}) ", action, keys.join(" "))
+        };
+        block = block.title(
+            Line::from(Span::styled(text, Style::default().fg(Color::Yellow))).right_aligned(),
+        );
+    }
+
     let footer = Paragraph::new(Line::from(vec![
         Span::styled(" ", Style::default()),
         Span::styled(shortcuts, Style::default().fg(Color::DarkGray)),
     ]))
-    .block(
-        Block::default()
-            .borders(Borders::ALL)
-            .border_type(BorderType::Rounded)
-            .border_style(Style::default().fg(Color::DarkGray)),
-    );
+    .block(block);
 
     frame.render_widget(footer, area);
 }
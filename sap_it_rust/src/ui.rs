@@ -122,12 +122,27 @@ pub fn error(message: &str) {
     eprintln!("{} {}", "✗".red(), message);
 }
 
+/// Display a hint on how to fix the preceding error.
+pub fn error_hint(message: &str) {
+    eprintln!("{} {}", "→".blue(), message);
+}
+
 /// Display connection info before connecting.
 pub fn display_connection_info(server: &Server, conn_type: ConnectionType) {
     println!();
     println!("{}", "Connection Details:".cyan().bold());
     println!("  Server: {}", server.name.white().bold());
-    println!("  VPN:    {}", server.vpn);
+    println!(
+        "  VPN:    {}",
+        if server.has_vpn() {
+            &server.vpn
+        } else {
+            "None"
+        }
+    );
+    if let Some(jump) = server.jump_host() {
+        println!("  Jump:   {}", jump);
+    }
     println!("  Type:   {}", conn_type.name());
 
     match conn_type {
@@ -0,0 +1,406 @@
+//! Configuration linting for `sap_it validate` and the TUI save path.
+//!
+//! `Config::load` only checks that the file deserializes. The validator also
+//! rejects mistakes serde lets through (unknown keys, duplicate names, bad
+//! addresses, zero timeouts) and reports each one with the line and column
+//! it was found at.
+
+use crate::config::Config;
+use std::collections::HashMap;
+use std::fmt;
+use std::net::IpAddr;
+use std::ops::Range;
+use toml_edit::{ImDocument, Item, TableLike};
+
+/// Keys allowed at the top level.
+const ROOT_KEYS: &[&str] = &["servers", "settings"];
+
+/// Keys allowed in `[settings]`.
+const SETTINGS_KEYS: &[&str] = &[
+    "vpn_timeout_secs",
+    "ping_timeout_ms",
+    "ping_retries",
+    "probe_method",
+    "probe_port",
+    "tcp_timeout_ms",
+    "status_interval_secs",
+    "ssh_mode",
+    "rdp_client",
+    "auto_save",
+];
+
+/// Keys allowed in a `[[servers]]` entry.
+const SERVER_KEYS: &[&str] = &[
+    "name",
+    "ssh",
+    "rdp",
+    "vpn",
+    "group",
+    "tags",
+    "jump",
+    "vpn_backend",
+    "probe_method",
+    "probe_port",
+];
+
+/// Keys allowed in `[servers.ssh]` and `[servers.jump]` tables.
+const SSH_KEYS: &[&str] = &["host", "port", "user", "identity_file", "options"];
+
+/// Keys allowed in a `[servers.rdp]` table.
+const RDP_KEYS: &[&str] = &[
+    "host",
+    "port",
+    "username",
+    "domain",
+    "resolution",
+    "fullscreen",
+    "multimon",
+    "clipboard",
+    "drives",
+    "gateway",
+    "cert",
+];
+
+/// Settings that must not be zero.
+const NONZERO_SETTINGS: &[&str] = &[
+    "vpn_timeout_secs",
+    "ping_timeout_ms",
+    "ping_retries",
+    "tcp_timeout_ms",
+];
+
+/// A problem found in the configuration source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Issue {
+    /// 1-based line.
+    pub line: usize,
+    /// 1-based column, in characters.
+    pub column: usize,
+    /// What is wrong.
+    pub message: String,
+}
+
+impl Issue {
+    /// Create an issue at the start of byte range `span` in `source`.
+    fn at(source: &str, span: Option<Range<usize>>, message: impl Into<String>) -> Self {
+        let offset = span.map_or(0, |span| span.start.min(source.len()));
+        let before = &source[..offset];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+
+        Self {
+            line,
+            column: before[line_start..].chars().count() + 1,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+/// Validate configuration source, returning every problem found.
+///
+/// Syntax and type errors stop validation early, since the remaining checks
+/// need a parsed configuration.
+pub fn validate(source: &str) -> Vec<Issue> {
+    let document = match ImDocument::parse(source) {
+        Ok(document) => document,
+        Err(e) => return vec![Issue::at(source, e.span(), e.message())],
+    };
+    let config: Config = match toml::from_str(source) {
+        Ok(config) => config,
+        Err(e) => return vec![Issue::at(source, e.span(), e.message())],
+    };
+
+    let mut validator = Validator {
+        source,
+        issues: Vec::new(),
+    };
+    validator.check(document.as_table(), &config);
+    validator
+        .issues
+        .sort_by_key(|issue| (issue.line, issue.column));
+    validator.issues
+}
+
+/// Collects issues while walking the document.
+struct Validator<'a> {
+    source: &'a str,
+    issues: Vec<Issue>,
+}
+
+impl Validator<'_> {
+    /// Record an issue at `span`.
+    fn report(&mut self, span: Option<Range<usize>>, message: impl Into<String>) {
+        self.issues.push(Issue::at(self.source, span, message));
+    }
+
+    /// Run all checks; `config` is the deserialized form of `root`.
+    fn check(&mut self, root: &dyn TableLike, config: &Config) {
+        self.unknown_keys(root, ROOT_KEYS, "the top level");
+
+        if let Some(settings) = root.get("settings").and_then(Item::as_table_like) {
+            self.unknown_keys(settings, SETTINGS_KEYS, "settings");
+            for &key in NONZERO_SETTINGS {
+                let value = settings.get(key);
+                if value.and_then(Item::as_integer) == Some(0) {
+                    self.report(value.and_then(Item::span), format!("{} must not be 0", key));
+                }
+            }
+        }
+
+        // `Config::load` refuses such a file, so it is reported at its start
+        if config.servers.is_empty() {
+            self.report(Some(0..0), "no servers defined; add a [[servers]] entry");
+        }
+
+        let servers = server_tables(root);
+        let mut names: HashMap<String, &str> = HashMap::new();
+        // Deserialization succeeded, so both lists describe the same servers
+        for (table, server) in servers.into_iter().zip(&config.servers) {
+            self.server(table, &server.name);
+
+            let name = table.get("name");
+            if server.name.trim().is_empty() {
+                self.report(name.and_then(Item::span), "name is empty");
+            } else if let Some(first) = names.insert(server.name.to_lowercase(), &server.name) {
+                self.report(
+                    name.and_then(Item::span),
+                    format!(
+                        "duplicate server name '{}' (also used by '{}')",
+                        server.name, first
+                    ),
+                );
+            }
+
+            if let Some(vpn) = table.get("vpn") {
+                if server.vpn.trim().is_empty() {
+                    self.report(
+                        vpn.span(),
+                        "vpn is empty; remove it for servers reached without a VPN",
+                    );
+                }
+            }
+
+            self.host(table, "rdp", Some(&server.rdp.host));
+            self.host(table, "ssh", server.ssh.as_ref().map(|s| s.host.as_str()));
+            self.host(table, "jump", server.jump.as_ref().map(|s| s.host.as_str()));
+        }
+    }
+
+    /// Check the keys of one server entry and its sub-tables.
+    fn server(&mut self, table: &dyn TableLike, name: &str) {
+        let context = format!("server '{}'", name);
+        self.unknown_keys(table, SERVER_KEYS, &context);
+
+        for (key, allowed) in [("ssh", SSH_KEYS), ("jump", SSH_KEYS), ("rdp", RDP_KEYS)] {
+            if let Some(sub) = table.get(key).and_then(Item::as_table_like) {
+                self.unknown_keys(sub, allowed, &format!("{} of {}", key, context));
+            }
+        }
+
+        if let Some(backend) = table.get("vpn_backend").and_then(Item::as_table_like) {
+            let allowed: &[&str] = match backend.get("type").and_then(Item::as_str) {
+                Some("openvpn") => &["type", "config", "args"],
+                Some("wg-quick") => &["type", "interface"],
+                Some("openconnect") => &["type", "server", "user", "protocol", "args"],
+                _ => &["type"],
+            };
+            self.unknown_keys(backend, allowed, &format!("vpn_backend of {}", context));
+        }
+    }
+
+    /// Report keys of `table` that are not in `allowed`.
+    fn unknown_keys(&mut self, table: &dyn TableLike, allowed: &[&str], context: &str) {
+        for (key, _) in table.iter() {
+            if !allowed.contains(&key) {
+                let span = table.key(key).and_then(|k| k.span());
+                self.report(span, format!("unknown key '{}' in {}", key, context));
+            }
+        }
+    }
+
+    /// Check the host of address field `key`, written as a string or table.
+    fn host(&mut self, table: &dyn TableLike, key: &str, host: Option<&str>) {
+        let (Some(item), Some(host)) = (table.get(key), host) else {
+            return;
+        };
+        let span = match item.as_table_like().and_then(|t| t.get("host")) {
+            Some(host_item) => host_item.span(),
+            None => item.span(),
+        };
+
+        if host.is_empty() {
+            // An empty ssh or jump means "not available"
+            if key == "rdp" {
+                self.report(span, "rdp host is empty");
+            }
+        } else if !is_valid_host(host) {
+            self.report(span, format!("invalid {} host '{}'", key, host));
+        }
+    }
+}
+
+/// Server entries, written as `[[servers]]` or as an inline array.
+fn server_tables(root: &dyn TableLike) -> Vec<&dyn TableLike> {
+    match root.get("servers") {
+        Some(Item::ArrayOfTables(tables)) => tables.iter().map(|t| t as &dyn TableLike).collect(),
+        Some(Item::Value(value)) => value
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|v| v.as_inline_table())
+            .map(|t| t as &dyn TableLike)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Whether `host` is an IP address or a valid DNS hostname.
+pub fn is_valid_host(host: &str) -> bool {
+    if host.parse::<IpAddr>().is_ok() {
+        return true;
+    }
+
+    let name = host.strip_suffix('.').unwrap_or(host);
+    let labels: Vec<&str> = name.split('.').collect();
+    // All-numeric names are malformed IPv4 addresses, e.g. 192.168.0.300
+    if labels.iter().all(|l| l.chars().all(|c| c.is_ascii_digit())) {
+        return false;
+    }
+
+    name.len() <= 253
+        && labels.iter().all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{RdpConfig, Server, SshConfig, VpnBackendConfig};
+
+    #[test]
+    fn test_valid_hosts() {
+        assert!(is_valid_host("192.168.0.99"));
+        assert!(is_valid_host("fe80::1"));
+        assert!(is_valid_host("bastion.example.com"));
+        assert!(is_valid_host("srv-01"));
+        assert!(!is_valid_host("192.168.0.300"));
+        assert!(!is_valid_host("bad_host"));
+        assert!(!is_valid_host("-leading.example.com"));
+        assert!(!is_valid_host("double..dot"));
+    }
+
+    #[test]
+    fn test_sample_config_is_valid() {
+        assert_eq!(validate(&Config::sample_toml()), Vec::new());
+    }
+
+    #[test]
+    fn test_serialized_config_is_valid() {
+        // Every key the structs can write must be known to the validator
+        let mut config = Config::default_config();
+        config.servers.push(Server {
+            name: "Everything".to_string(),
+            ssh: Some(SshConfig {
+                host: "10.0.0.1".to_string(),
+                port: Some(2222),
+                user: Some("root".to_string()),
+                identity_file: Some("~/.ssh/id".into()),
+                options: vec!["ServerAliveInterval=30".to_string()],
+            }),
+            rdp: RdpConfig {
+                host: "10.0.0.2".to_string(),
+                port: Some(3390),
+                username: Some("admin".to_string()),
+                domain: Some("CORP".to_string()),
+                resolution: Some("1920x1080".to_string()),
+                fullscreen: true,
+                multimon: true,
+                clipboard: false,
+                drives: vec!["/tmp".to_string()],
+                gateway: Some("gw.example.com".to_string()),
+                cert: crate::config::CertPolicy::Strict,
+            },
+            vpn: "corp".to_string(),
+            group: Some("Corp".to_string()),
+            tags: vec!["prod".to_string()],
+            jump: Some("admin@bastion.example.com".parse().unwrap()),
+            vpn_backend: Some(VpnBackendConfig::OpenConnect {
+                server: "vpn.example.com".to_string(),
+                user: Some("me".to_string()),
+                protocol: Some("gp".to_string()),
+                args: vec!["--quiet".to_string()],
+            }),
+            probe_method: Some(crate::config::ProbeMethod::Tcp),
+            probe_port: Some(22),
+        });
+
+        let source = toml::to_string_pretty(&config).unwrap();
+        assert_eq!(validate(&source), Vec::new());
+    }
+
+    #[test]
+    fn test_issues_have_locations() {
+        let source = r#"[settings]
+vpn_timeout_secs = 0
+colour = "blue"
+
+[[servers]]
+name = "ALPI"
+rdp = "192.168.0.300"
+vpn = ""
+
+[[servers]]
+name = "alpi"
+rdp = { host = "10.0.0.1", colour = "red" }
+ssh = "root@bad_host"
+"#;
+        let issues: Vec<String> = validate(source).iter().map(Issue::to_string).collect();
+        assert_eq!(
+            issues,
+            vec![
+                "line 2, column 20: vpn_timeout_secs must not be 0",
+                "line 3, column 1: unknown key 'colour' in settings",
+                "line 7, column 7: invalid rdp host '192.168.0.300'",
+                "line 8, column 7: vpn is empty; remove it for servers reached without a VPN",
+                "line 11, column 8: duplicate server name 'alpi' (also used by 'ALPI')",
+                "line 12, column 28: unknown key 'colour' in rdp of server 'alpi'",
+                "line 13, column 7: invalid ssh host 'bad_host'",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_no_servers() {
+        let issues: Vec<String> = validate("[settings]\nping_retries = 3\n")
+            .iter()
+            .map(Issue::to_string)
+            .collect();
+        assert_eq!(
+            issues,
+            vec!["line 1, column 1: no servers defined; add a [[servers]] entry"]
+        );
+        assert_eq!(validate("").len(), 1);
+    }
+
+    #[test]
+    fn test_syntax_errors_have_locations() {
+        let issues = validate("[settings]\nping_retries = \n");
+        assert_eq!(issues.len(), 1);
+        assert_eq!((issues[0].line, issues[0].column), (2, 16));
+    }
+}
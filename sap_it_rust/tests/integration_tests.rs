@@ -153,3 +153,206 @@ fn test_connect_invalid_type() {
     let stderr = String::from_utf8_lossy(&output.stderr);
     assert!(stderr.contains("Invalid connection type") || stderr.contains("invalid"));
 }
+
+/// Test that connect --json ends with a failed event for errors found
+/// before connecting.
+#[test]
+fn test_connect_json_reports_no_ssh() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let config_path = temp_dir.path().join("servers.toml");
+    std::fs::write(
+        &config_path,
+        "[[servers]]\nname = \"RdpOnly\"\nrdp = \"192.168.1.1\"\n",
+    )
+    .expect("Failed to write config");
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "--config",
+            config_path.to_str().unwrap(),
+            "connect",
+            "RdpOnly",
+            "-t",
+            "ssh",
+            "--json",
+        ])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .expect("Failed to execute command");
+
+    assert_eq!(output.status.code(), Some(8));
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 1, "expected one event, got {:?}", lines);
+    let event: serde_json::Value = serde_json::from_str(lines[0]).expect("Invalid JSON event");
+    assert_eq!(event["event"], "failed");
+    assert_eq!(event["exit_code"], 8);
+    assert!(event["hint"].as_str().is_some_and(|hint| !hint.is_empty()));
+}
+
+/// Test the list subcommand filters by group and tag.
+#[test]
+fn test_list_filter_by_group_and_tag() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let config_path = temp_dir.path().join("servers.toml");
+
+    let config_content = r#"
+[[servers]]
+name = "AcmeProd"
+rdp = "192.168.1.1"
+vpn = "ACME"
+group = "Acme"
+tags = ["prod"]
+
+[[servers]]
+name = "AcmeTest"
+rdp = "192.168.1.2"
+vpn = "ACME"
+group = "Acme"
+tags = ["test"]
+
+[[servers]]
+name = "OtherProd"
+rdp = "192.168.2.1"
+vpn = "OTHER"
+tags = ["prod"]
+"#;
+
+    std::fs::write(&config_path, config_content).expect("Failed to write config");
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "--config",
+            config_path.to_str().unwrap(),
+            "list",
+            "--group",
+            "acme",
+            "--tag",
+            "prod",
+        ])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "List command failed");
+    assert!(stdout.contains("AcmeProd"));
+    assert!(!stdout.contains("AcmeTest"));
+    assert!(!stdout.contains("OtherProd"));
+}
+
+/// Test the validate subcommand reports problems with their location.
+#[test]
+fn test_validate_reports_locations() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let config_path = temp_dir.path().join("servers.toml");
+
+    let config = r#"
+[[servers]]
+name = "Ilmatex"
+rdp = "192.168.0.99"
+
+[[servers]]
+name = "Ilmatex"
+rdp = "192.168.0.100"
+"#;
+    std::fs::write(&config_path, config).expect("Failed to write config");
+
+    let output = Command::new("cargo")
+        .args(["run", "--", "validate", config_path.to_str().unwrap()])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(!output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(":7:8: duplicate server name 'Ilmatex'"));
+}
+
+/// Test that the server subcommand keeps comments in the config file.
+#[test]
+fn test_server_edits_keep_comments() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let config_path = temp_dir.path().join("servers.toml");
+
+    let config = r#"# Production servers
+[[servers]]
+name = "Ilmatex"
+rdp = "192.168.0.99" # behind the ILMATEX VPN
+"#;
+    std::fs::write(&config_path, config).expect("Failed to write config");
+
+    let server = |args: &[&str]| {
+        let output = Command::new("cargo")
+            .args([
+                "run",
+                "--",
+                "--config",
+                config_path.to_str().unwrap(),
+                "server",
+            ])
+            .args(args)
+            .current_dir(env!("CARGO_MANIFEST_DIR"))
+            .output()
+            .expect("Failed to execute command");
+        output.status.success()
+    };
+
+    assert!(server(&["add", "Nova", "--rdp", "10.0.0.1"]));
+    assert!(server(&["edit", "ilmatex", "--vpn", "ILMATEX"]));
+    assert!(server(&["mv", "Nova", "Nova-Old"]));
+    assert!(!server(&["mv", "Nova-Old", "Ilmatex"]));
+    assert!(server(&["rm", "Nova-Old"]));
+
+    let content = std::fs::read_to_string(&config_path).expect("Failed to read config");
+    assert_eq!(
+        content,
+        r#"# Production servers
+[[servers]]
+name = "Ilmatex"
+rdp = "192.168.0.99" # behind the ILMATEX VPN
+vpn = "ILMATEX"
+"#
+    );
+}
+
+/// Test the list subcommand's machine-readable formats.
+#[test]
+fn test_list_formats() {
+    let config_path = concat!(env!("CARGO_MANIFEST_DIR"), "/servers.example.toml");
+
+    let list = |format: &str| {
+        let output = Command::new("cargo")
+            .args([
+                "run",
+                "--",
+                "--config",
+                config_path,
+                "list",
+                "--format",
+                format,
+            ])
+            .current_dir(env!("CARGO_MANIFEST_DIR"))
+            .output()
+            .expect("Failed to execute command");
+        assert!(output.status.success(), "list --format {} failed", format);
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    let json = list("json");
+    assert!(json.trim_start().starts_with('['));
+    assert!(json.contains(r#""name": "Ilmatex""#));
+
+    let csv = list("csv");
+    assert!(csv.starts_with("index,name,group,vpn,rdp,ssh,jump,tags\n"));
+    assert!(csv.contains("1,Ilmatex,,ILMATEX,192.168.0.99,root@192.168.0.98,,"));
+
+    assert!(list("yaml").contains("- index: 1"));
+}